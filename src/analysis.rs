@@ -0,0 +1,395 @@
+#![allow(dead_code)]
+
+use std::collections::HashSet;
+
+use indexmap::IndexMap;
+
+use crate::common::Origin;
+use crate::parser::APIStmt;
+use crate::struct_ref::{Field, FieldType, StructDef, TypeAlias};
+
+/// A problem found in a parsed API, pointing at the offending definition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub origin: Origin,
+    pub help: Option<String>,
+}
+
+/// The types an API declares, by name. A name declared twice maps to its
+/// last declaration, `analyze` reports the duplicate.
+#[derive(Debug, Default)]
+pub struct SymbolTable<'a> {
+    pub structs: IndexMap<&'a str, &'a StructDef>,
+    pub aliases: IndexMap<&'a str, &'a TypeAlias>,
+}
+
+impl<'a> SymbolTable<'a> {
+    pub fn new(api: &'a APIStmt) -> Self {
+        SymbolTable {
+            structs: api
+                .type_struct
+                .iter()
+                .map(|s| (s.name.as_str(), s))
+                .collect(),
+            aliases: api
+                .type_alias
+                .iter()
+                .map(|a| (a.name.as_str(), a))
+                .collect(),
+        }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.structs.contains_key(name) || self.aliases.contains_key(name)
+    }
+
+    /// Follows references to aliases down to the type they name, `UserID` to
+    /// `int64`. An alias cycle stops after visiting every alias.
+    pub fn resolve<'t>(&'t self, field_type: &'t FieldType) -> &'t FieldType {
+        let mut resolved = field_type;
+        for _ in 0..=self.aliases.len() {
+            let FieldType::StructRef { name, .. } = resolved else {
+                break;
+            };
+            match self.aliases.get(name.as_str()) {
+                Some(alias) => resolved = &alias.target,
+                None => break,
+            }
+        }
+        resolved
+    }
+
+    /// The struct a type name stands for, following aliases as in `type Req = Base`.
+    pub fn struct_named(&self, name: &str) -> Option<&'a StructDef> {
+        let target = match self.aliases.get(name) {
+            Some(alias) => self.resolve(&alias.target),
+            None => return self.structs.get(name).copied(),
+        };
+        match target {
+            FieldType::StructRef { name, .. } => self.structs.get(name.as_str()).copied(),
+            _ => None,
+        }
+    }
+}
+
+/// An API whose type names are unique, whose type references all name a
+/// declared type and whose types only recurse through slices, pointers and
+/// maps, what generators can rely on.
+#[derive(Debug)]
+pub struct ResolvedApi<'a> {
+    pub api: &'a APIStmt,
+    pub types: SymbolTable<'a>,
+}
+
+/// Builds the symbol table of `api` and checks every type reference of its
+/// fields, aliases and handlers against it, then looks for types containing
+/// themselves by value. Diagnostics come in source order.
+pub fn analyze(api: &APIStmt) -> Result<ResolvedApi<'_>, Vec<Diagnostic>> {
+    let types = SymbolTable::new(api);
+    let mut diagnostics = duplicate_types(api);
+    diagnostics.extend(recursive_types(&types));
+
+    for struct_def in &api.type_struct {
+        check_fields(&types, &struct_def.fields, &mut diagnostics);
+    }
+    for alias in &api.type_alias {
+        check_type(&types, &alias.target, &alias.origin, &mut diagnostics);
+    }
+    for handler in api.services.iter().flat_map(|s| &s.handlers) {
+        let references = [
+            (&handler.req_type, &handler.req_origin),
+            (&handler.resp_type, &handler.resp_origin),
+        ];
+        for (name, origin) in references {
+            if let (Some(name), Some(origin)) = (name, origin) {
+                if !types.contains(name) {
+                    diagnostics.push(undefined_type(name, origin));
+                }
+            }
+        }
+    }
+
+    if !diagnostics.is_empty() {
+        diagnostics.sort_by_key(|d| (d.origin.file, d.origin.span.start));
+        return Err(diagnostics);
+    }
+    Ok(ResolvedApi { api, types })
+}
+
+// duplicate_types reports every declaration of a name after the first, structs
+// and aliases share one namespace across the API and its imports.
+fn duplicate_types(api: &APIStmt) -> Vec<Diagnostic> {
+    let declarations = api
+        .type_struct
+        .iter()
+        .map(|s| (&s.name, &s.origin))
+        .chain(api.type_alias.iter().map(|a| (&a.name, &a.origin)));
+
+    let mut declared = IndexMap::new();
+    let mut diagnostics = vec![];
+    for (name, origin) in declarations {
+        if declared.insert(name, origin).is_some() {
+            diagnostics.push(Diagnostic {
+                message: format!("duplicate type `{name}`"),
+                origin: origin.clone(),
+                help: Some(
+                    "type names must be unique across the API file and its imports".to_string(),
+                ),
+            });
+        }
+    }
+    diagnostics
+}
+
+fn check_fields(types: &SymbolTable, fields: &[Field], diagnostics: &mut Vec<Diagnostic>) {
+    for field in fields {
+        check_type(types, &field.field_type, &field.type_origin, diagnostics);
+    }
+}
+
+fn check_type(
+    types: &SymbolTable,
+    field_type: &FieldType,
+    origin: &Origin,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    match field_type {
+        FieldType::StructRef { name, .. } if !types.contains(name) => {
+            diagnostics.push(undefined_type(name, origin));
+        }
+        FieldType::Array(inner) | FieldType::FixedArray(_, inner) | FieldType::Pointer(inner) => {
+            check_type(types, inner, origin, diagnostics)
+        }
+        FieldType::Map(key_type, value_type) => {
+            check_type(types, key_type, origin, diagnostics);
+            check_type(types, value_type, origin, diagnostics);
+        }
+        FieldType::Inline(fields) => check_fields(types, fields, diagnostics),
+        _ => {}
+    }
+}
+
+// A reference from one type to another that makes the second part of the first
+// one's value: an embed, a struct typed field, an array element or an alias target.
+struct Edge<'a> {
+    target: &'a str,
+    origin: &'a Origin,
+    embed: bool,
+}
+
+// recursive_types reports every cycle of by value references, such a type would
+// be infinitely large and a generator flattening its embeds would never stop.
+// Slices, pointers and maps break a cycle, those stay plain `$ref`s.
+fn recursive_types(types: &SymbolTable) -> Vec<Diagnostic> {
+    let mut done = HashSet::new();
+    let mut path = vec![];
+    let mut diagnostics = vec![];
+    for &name in types.structs.keys().chain(types.aliases.keys()) {
+        visit(types, name, &mut done, &mut path, &mut diagnostics);
+    }
+    diagnostics
+}
+
+// visit walks the references of `name` depth first, `path` holds the types being
+// visited with the edge leading out of each, so a reference back into it closes
+// a cycle.
+fn visit<'a>(
+    types: &SymbolTable<'a>,
+    name: &'a str,
+    done: &mut HashSet<&'a str>,
+    path: &mut Vec<(&'a str, Edge<'a>)>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    if done.contains(name) {
+        return;
+    }
+    for edge in value_edges(types, name) {
+        let target = edge.target;
+        path.push((name, edge));
+        match path.iter().position(|(from, _)| *from == target) {
+            Some(start) => diagnostics.push(recursive_type(&path[start..])),
+            None => visit(types, target, done, path, diagnostics),
+        }
+        path.pop();
+    }
+    done.insert(name);
+}
+
+fn value_edges<'a>(types: &SymbolTable<'a>, name: &str) -> Vec<Edge<'a>> {
+    let mut edges = vec![];
+    if let Some(def) = types.structs.get(name) {
+        for field in &def.fields {
+            type_edges(&field.field_type, &field.type_origin, &mut edges);
+        }
+    } else if let Some(alias) = types.aliases.get(name) {
+        type_edges(&alias.target, &alias.origin, &mut edges);
+    }
+    edges
+}
+
+fn type_edges<'a>(field_type: &'a FieldType, origin: &'a Origin, edges: &mut Vec<Edge<'a>>) {
+    match field_type {
+        FieldType::StructRef { name, is_embed } => edges.push(Edge {
+            target: name,
+            origin,
+            embed: *is_embed,
+        }),
+        FieldType::FixedArray(_, inner) => type_edges(inner, origin, edges),
+        FieldType::Inline(fields) => {
+            for field in fields {
+                type_edges(&field.field_type, &field.type_origin, edges);
+            }
+        }
+        _ => {}
+    }
+}
+
+// recursive_type reports a cycle at the reference leaving its first type.
+fn recursive_type(cycle: &[(&str, Edge)]) -> Diagnostic {
+    let names = cycle
+        .iter()
+        .map(|(from, _)| *from)
+        .chain([cycle[0].0])
+        .collect::<Vec<_>>()
+        .join(" -> ");
+    let (message, help) = if cycle.iter().all(|(_, edge)| edge.embed) {
+        (
+            format!("embed cycle `{names}`"),
+            "a struct cannot embed itself, directly or through other embeds; \
+             use a named field such as `Parent *T` instead",
+        )
+    } else {
+        (
+            format!("invalid recursive type `{names}`"),
+            "a type cannot contain itself by value; \
+             reference it through a slice, pointer or map such as `[]T` or `*T`",
+        )
+    };
+    Diagnostic {
+        message,
+        origin: cycle[0].1.origin.clone(),
+        help: Some(help.to_string()),
+    }
+}
+
+fn undefined_type(name: &str, origin: &Origin) -> Diagnostic {
+    Diagnostic {
+        message: format!("undefined type `{name}`"),
+        origin: origin.clone(),
+        help: Some(format!(
+            "declare it with `type {name} struct {{ ... }}` or import the file declaring it"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_api;
+    use crate::token::tokenize;
+
+    #[test]
+    fn it_analyze() {
+        let source = r#"
+        type UserID int64
+        type User struct {
+            ID      UserID            `json:"id"`
+            Friends []*User           `json:"friends"`
+            Labels  map[string]Label  `json:"labels"`
+            Extra   struct {
+                Owner Owner `json:"owner"`
+            } `json:"extra"`
+        }
+        type User struct {
+            Name string `json:"name"`
+        }
+        service user {
+            @handler getUser
+            get /users/:id (GetUserReq) returns (User)
+        }
+        "#;
+        let input = tokenize(source);
+        let api = parse_api(&input).unwrap().1;
+
+        let diagnostics = analyze(&api).unwrap_err();
+        let found = diagnostics
+            .iter()
+            .map(|d| (d.message.as_str(), &source[d.origin.span.clone()]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            vec![
+                ("undefined type `Label`", "map[string]Label"),
+                ("undefined type `Owner`", "Owner"),
+                ("duplicate type `User`", "User"),
+                ("undefined type `GetUserReq`", "GetUserReq"),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_analyze_resolves() {
+        let source = r#"
+        type UserID int64
+        type ID = UserID
+        type User struct {
+            ID ID `json:"id"`
+        }
+        service user {
+            @handler getUser
+            get /users (User) returns (User)
+        }
+        "#;
+        let input = tokenize(source);
+        let api = parse_api(&input).unwrap().1;
+
+        let resolved = analyze(&api).unwrap();
+        let field_type = &resolved.types.structs["User"].fields[0].field_type;
+        assert_eq!(resolved.types.resolve(field_type), &FieldType::Int64);
+    }
+
+    #[test]
+    fn it_analyze_recursive_types() {
+        let source = r#"
+        type Node struct {
+            Value    int             `json:"value"`
+            Children []Node          `json:"children"`
+            Parent   *Node           `json:"parent"`
+            Index    map[string]Node `json:"index"`
+        }
+        type A struct {
+            B
+        }
+        type B struct {
+            A
+        }
+        type Loop struct {
+            Extra struct {
+                Next Loop `json:"next"`
+            } `json:"extra"`
+        }
+        type Self struct {
+            Self
+        }
+        type Ring [2]Ring
+        "#;
+        let input = tokenize(source);
+        let api = parse_api(&input).unwrap().1;
+
+        let diagnostics = analyze(&api).unwrap_err();
+        let found = diagnostics
+            .iter()
+            .map(|d| (d.message.as_str(), &source[d.origin.span.clone()]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            vec![
+                ("embed cycle `A -> B -> A`", "B"),
+                ("invalid recursive type `Loop -> Loop`", "Loop"),
+                ("embed cycle `Self -> Self`", "Self"),
+                ("invalid recursive type `Ring -> Ring`", "Ring"),
+            ]
+        );
+    }
+}
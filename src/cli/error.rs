@@ -12,9 +12,21 @@ pub enum TransformError {
     #[error("Failed to read file {0}")]
     IOError(#[from] std::io::Error),
 
-    #[error("Failed to parse at {0}")]
+    #[error("Failed to resolve path '{path}': {source}")]
+    ResolvePathError {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("Failed to parse API file\n{0}")]
     ParseError(String),
 
+    #[error("Invalid API definition\n{0}")]
+    AnalysisError(String),
+
+    #[error("Failed to generate output\n{0}")]
+    GenerateError(String),
+
     #[error("Failed to create output directory {0}")]
     OutDirError(PathBuf),
 
@@ -30,3 +42,24 @@ pub enum TransformError {
         source: std::io::Error,
     },
 }
+
+impl TransformError {
+    /// Process exit code for the error, following the BSD `sysexits.h` conventions
+    /// so that Makefiles and scripts can tell usage, input and output failures apart.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            // EX_USAGE
+            TransformError::UnsupportedCommand => 64,
+            // EX_DATAERR
+            TransformError::ParseError(_)
+            | TransformError::AnalysisError(_)
+            | TransformError::GenerateError(_) => 65,
+            // EX_NOINPUT
+            TransformError::ResolvePathError { .. } => 66,
+            // EX_CANTCREAT
+            TransformError::OutDirError(_) | TransformError::CreateFileError { .. } => 73,
+            // EX_IOERR
+            TransformError::IOError(_) | TransformError::WriteFileError { .. } => 74,
+        }
+    }
+}
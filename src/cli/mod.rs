@@ -1,13 +1,15 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub mod oli;
 
 mod error;
 
-fn canonicalize_path(path: PathBuf) -> PathBuf {
-    path.canonicalize().unwrap_or_else(|e| {
-        let path_str = path.to_str().unwrap_or("Invalid path");
-        eprintln!("Error resolving path '{}': {}", path_str, e);
-        std::process::exit(1);
-    })
+pub use error::TransformError;
+
+fn canonicalize_path(path: &Path) -> Result<PathBuf, TransformError> {
+    path.canonicalize()
+        .map_err(|source| TransformError::ResolvePathError {
+            path: path.to_path_buf(),
+            source,
+        })
 }
@@ -2,14 +2,22 @@
 
 use std::fs;
 use std::io::Write;
-use std::{fs::File, path::PathBuf};
+use std::process::ExitCode;
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+};
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 
 use super::canonicalize_path;
 use super::error::TransformError;
-use crate::{openapi::swagger::to_swagger, parser::parse_api, token::tokenize};
+use crate::{
+    analysis::analyze,
+    loader::{load_api, LoadError},
+    openapi::swagger::{to_swagger_with, OpenApiVersion, SwaggerOptions},
+};
 
 // goctl oai swagger --api <api file> -dir <output dir>
 #[derive(Parser)]
@@ -26,6 +34,7 @@ pub struct Goctl {
 
 #[derive(Subcommand)]
 enum Command {
+    /// Generate OpenAPI documents from an API file
     Oai {
         #[clap(subcommand)]
         command: OaiCommand,
@@ -34,15 +43,42 @@ enum Command {
 
 #[derive(Subcommand)]
 enum OaiCommand {
+    /// Generate an OpenAPI 3 (Swagger) json document, written as gen_swagger.json
     Swagger {
+        /// The API file to read
         #[clap(long = "api", short = 'a', default_value = "example.api")]
         input_api: String,
+        /// The directory gen_swagger.json is written to, created if missing
         #[clap(long = "dir", short = 'd', default_value = ".")]
         output_dir: String,
+        /// Name of the bearer security scheme of `jwt` services [default: the jwt value]
+        #[clap(long = "jwt-scheme")]
+        jwt_scheme: Option<String>,
+        /// Name of the API key security scheme of `signature` services [default: signature]
+        #[clap(long = "signature-scheme")]
+        signature_scheme: Option<String>,
+        /// Inline the fields of embedded structs instead of composing them with `allOf`
+        #[clap(long = "flatten-embeds")]
+        flatten_embeds: bool,
+        /// The OpenAPI version of the document
+        #[clap(long = "openapi", value_enum, default_value = "3.0")]
+        openapi_version: OpenApiVersion,
     },
 }
 
-fn run_oli() -> Result<(), TransformError> {
+/// Entry point of the `goctl` binary: runs the parsed command and maps the
+/// outcome to a process exit code.
+pub fn run() -> ExitCode {
+    match run_oli() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+pub fn run_oli() -> Result<(), TransformError> {
     let goctl = Goctl::parse();
 
     match goctl.command {
@@ -51,35 +87,64 @@ fn run_oli() -> Result<(), TransformError> {
                 OaiCommand::Swagger {
                     input_api,
                     output_dir,
+                    jwt_scheme,
+                    signature_scheme,
+                    flatten_embeds,
+                    openapi_version,
                 },
         } => {
-            convert_to_swagger(&PathBuf::from(&input_api), &PathBuf::from(&output_dir))?;
+            let options = SwaggerOptions {
+                jwt_scheme,
+                signature_scheme,
+                flatten_embeds,
+                openapi_version,
+            };
+            convert_to_swagger(
+                &PathBuf::from(&input_api),
+                &PathBuf::from(&output_dir),
+                &options,
+            )?;
             Ok(())
         }
     }
 }
 
-fn convert_to_swagger(input_api: &PathBuf, output_dir: &PathBuf) -> Result<String, TransformError> {
-    let (api_path, output_dir) = (
-        canonicalize_path(PathBuf::from(input_api)),
-        canonicalize_path(PathBuf::from(output_dir)),
-    );
-
-    fs::create_dir_all(&output_dir).map_err(|_| TransformError::OutDirError(output_dir.clone()))?;
-
-    let source = fs::read_to_string(&api_path).unwrap_or_else(|e| {
-        eprintln!("Error reading file '{}': {}", api_path.to_str().unwrap(), e);
-        std::process::exit(1);
-    });
-    let (_, api_data) =
-        parse_api(&(tokenize(&source))).map_err(|e| TransformError::ParseError(e.to_string()))?;
-
-    let swagger_json = to_swagger(api_data);
+pub fn convert_to_swagger(
+    input_api: &Path,
+    output_dir: &Path,
+    options: &SwaggerOptions,
+) -> Result<String, TransformError> {
+    let project = load_api(input_api).map_err(|e| match e {
+        LoadError::Io { path, source } => TransformError::ResolvePathError { path, source },
+        e => TransformError::ParseError(e.to_string()),
+    })?;
+    let resolved = analyze(&project.api).map_err(|diagnostics| {
+        let rendered = diagnostics
+            .iter()
+            .map(|d| project.render(&d.origin, &d.message, d.help.as_deref()))
+            .collect::<Vec<_>>();
+        TransformError::AnalysisError(rendered.join("\n"))
+    })?;
+
+    fs::create_dir_all(output_dir)
+        .map_err(|_| TransformError::OutDirError(output_dir.to_path_buf()))?;
+    let output_dir = canonicalize_path(output_dir)?;
+
+    let swagger_json = to_swagger_with(&resolved, options).map_err(|e| {
+        TransformError::GenerateError(project.render(e.origin(), &e.to_string(), None))
+    })?;
 
     // Write to file
-    let output_path = PathBuf::from(&output_dir).join("gen_swagger.json");
-    let mut output_file = File::create(&output_path)?;
-    writeln!(output_file, "{}", swagger_json.to_string())?;
+    let output_path = output_dir.join("gen_swagger.json");
+    let mut output_file =
+        File::create(&output_path).map_err(|source| TransformError::CreateFileError {
+            path: output_path.display().to_string(),
+            source,
+        })?;
+    writeln!(output_file, "{}", swagger_json).map_err(|source| TransformError::WriteFileError {
+        path: output_path.display().to_string(),
+        source,
+    })?;
 
     Ok(swagger_json.to_string())
 }
@@ -106,11 +171,41 @@ mod tests {
                 OaiCommand::Swagger {
                     input_api,
                     output_dir,
+                    ..
                 } => {
-                    convert_to_swagger(&PathBuf::from(&input_api), &PathBuf::from(&output_dir))
-                        .expect("Failed to convert to swagger");
+                    convert_to_swagger(
+                        &PathBuf::from(&input_api),
+                        &PathBuf::from(&output_dir),
+                        &SwaggerOptions::default(),
+                    )
+                    .expect("Failed to convert to swagger");
                 }
             },
         }
     }
+
+    #[test]
+    fn test_missing_api_exit_code() {
+        let err = convert_to_swagger(
+            &PathBuf::from("tests/missing.api"),
+            &PathBuf::from("tests"),
+            &SwaggerOptions::default(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, TransformError::ResolvePathError { .. }));
+        assert_eq!(err.exit_code(), 66);
+    }
+
+    #[test]
+    fn test_help_lists_subcommands() {
+        use clap::CommandFactory;
+
+        let mut cmd = Goctl::command();
+        let help = cmd.render_long_help().to_string();
+        assert!(help.contains("oai"));
+
+        let oai = cmd.find_subcommand_mut("oai").unwrap();
+        let help = oai.render_long_help().to_string();
+        assert!(help.contains("swagger"));
+    }
 }
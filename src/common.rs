@@ -1,43 +1,129 @@
 use crate::{
-    error::{self, PError},
-    token::{APIToken, APITokenKind},
+    error::{self, Expected, PError},
+    token::{comment_text, APIToken, APITokenKind},
 };
 use nom::Slice;
+use std::ops::Range;
 
 pub type Input<'a> = &'a [APIToken<'a>];
 pub type IResult<'a, Output> = nom::IResult<Input<'a>, Output, error::PError>;
 
+/// Where a definition was declared: the index of its file in a loaded
+/// project (always 0 for a single parsed file) and its span in that file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Origin {
+    pub file: usize,
+    pub span: Range<usize>,
+}
+
+impl Origin {
+    pub fn of(token: &APIToken) -> Self {
+        Origin {
+            file: 0,
+            span: token.span.clone(),
+        }
+    }
+}
+
+/// The tokens of `input` a parser consumed to leave `rest`.
+pub fn consumed<'a>(input: Input<'a>, rest: Input<'a>) -> Input<'a> {
+    &input[..input.len() - rest.len()]
+}
+
+/// Comments documenting a construct written on one or two lines, like a field
+/// or a handler: the comments leading its first token and those trailing any of its tokens.
+pub fn docs_of(tokens: Input) -> Vec<String> {
+    let leading = tokens.first().map(|t| t.docs()).unwrap_or_default();
+    let trailing = tokens
+        .iter()
+        .filter_map(|t| t.trailing_comment.map(comment_text));
+    leading.into_iter().chain(trailing).collect()
+}
+
 pub fn match_token(kind: APITokenKind) -> impl Fn(Input) -> IResult<&APIToken> {
-    move |i: Input| match i.get(0) {
+    move |i: Input| match i.first() {
         Some(token) if token.kind == kind => Ok((i.slice(1..), token)),
-        Some(token) => Err(nom::Err::Error(PError(format!(
-            "Expected API Token {kind}, found {} at {}",
-            token.kind, token.at
-        )))),
-        _ => Err(nom::Err::Error(PError(format!(
-            "API Token {:?} does not match",
-            kind
-        )))),
+        _ => Err(nom::Err::Error(PError::expected(
+            i,
+            Expected::Kind(kind.clone()),
+        ))),
     }
 }
 
 pub fn match_text(text: &'static str) -> impl Fn(Input) -> IResult<&APIToken> {
-    move |i| match i.get(0).filter(|token| token.text() == text) {
+    move |i| match i.first().filter(|token| token.text() == text) {
         Some(token) => Ok((i.slice(1..), token)),
-        None => Err(nom::Err::Error(PError(format!(
-            "Json Text {text} does not match",
-        )))),
+        None => Err(nom::Err::Error(PError::expected(i, Expected::Text(text)))),
     }
 }
 
 pub fn match_text_case_insensitive(text: &'static str) -> impl Fn(Input) -> IResult<&APIToken> {
     move |i| match i
-        .get(0)
+        .first()
         .filter(|token| token.text().eq_ignore_ascii_case(text))
     {
         Some(token) => Ok((i.slice(1..), token)),
-        None => Err(nom::Err::Error(PError(format!(
-            "Json Text {text} does not match",
-        )))),
+        None => Err(nom::Err::Error(PError::expected(i, Expected::Text(text)))),
+    }
+}
+
+/// Repeats `item` until the `close` token, which is consumed but not returned.
+///
+/// Unlike `terminated(many0(item), match_token(close))`, an item that fails
+/// is not silently dropped: the error reports what either the item or the
+/// closing token expected at that point, and is a failure so that callers
+/// do not backtrack past the already opened block.
+pub fn many_till_token<'a, O, F>(
+    mut item: F,
+    close: APITokenKind,
+) -> impl FnMut(Input<'a>) -> IResult<'a, Vec<O>>
+where
+    F: nom::Parser<Input<'a>, O, PError>,
+{
+    move |mut i: Input<'a>| {
+        let mut items = Vec::new();
+        loop {
+            let close_err = match match_token(close.clone())(i) {
+                Ok((rest, _)) => return Ok((rest, items)),
+                Err(nom::Err::Error(e)) => e,
+                Err(e) => return Err(e),
+            };
+            match item.parse(i) {
+                Ok((rest, _)) if rest.len() == i.len() => {
+                    return Err(nom::Err::Failure(close_err));
+                }
+                Ok((rest, o)) => {
+                    items.push(o);
+                    i = rest;
+                }
+                Err(nom::Err::Error(e)) => {
+                    return Err(nom::Err::Failure(nom::error::ParseError::or(close_err, e)))
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// The value of a `StringLiteral` token, without quotes and with escapes resolved.
+pub fn unquote(literal: &str) -> String {
+    let inner = literal
+        .strip_prefix('"')
+        .and_then(|l| l.strip_suffix('"'))
+        .unwrap_or(literal);
+
+    let mut value = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match (c, c == '\\') {
+            (_, true) => match chars.next() {
+                Some('n') => value.push('\n'),
+                Some('t') => value.push('\t'),
+                Some(escaped) => value.push(escaped),
+                None => value.push('\\'),
+            },
+            (c, false) => value.push(c),
+        }
     }
+    value
 }
@@ -1,22 +1,292 @@
 #![allow(dead_code)]
 
-use crate::common::{IResult, Input};
+use std::ops::Range;
 
-#[derive(Debug)]
-pub struct PError(pub String);
+use crate::{
+    common::{IResult, Input},
+    token::{APIToken, APITokenKind},
+};
+
+/// What the parser was looking for when it failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expected {
+    Kind(APITokenKind),
+    Text(&'static str),
+    /// A construct made of several tokens, e.g. `a field type`.
+    Desc(&'static str),
+}
+
+impl std::fmt::Display for Expected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expected::Kind(kind) => write!(f, "{kind}"),
+            Expected::Text(text) => write!(f, "`{text}`"),
+            Expected::Desc(desc) => write!(f, "{desc}"),
+        }
+    }
+}
+
+/// Parse error pointing at the offending token.
+///
+/// `span` is a byte range into the source the tokens were lexed from,
+/// `None` means the input ended before the parser was done. The optional
+/// `found` text and `help` are boxed to keep `nom::Err<PError>` small, it is
+/// returned by every parser.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PError {
+    pub message: String,
+    pub span: Option<Range<usize>>,
+    pub expected: Vec<Expected>,
+    pub found: Option<(APITokenKind, Box<str>)>,
+    pub help: Option<Box<str>>,
+}
 
 impl PError {
-    pub fn from<O>(msg: &str) -> IResult<O> {
-        Err(nom::Err::Error(PError(msg.to_string())))
+    pub fn new(message: impl Into<String>) -> Self {
+        PError {
+            message: message.into(),
+            span: None,
+            expected: vec![],
+            found: None,
+            help: None,
+        }
+    }
+
+    pub fn from<O>(msg: &str) -> IResult<'_, O> {
+        Err(nom::Err::Error(PError::new(msg)))
+    }
+
+    /// An error raised at `token`, with a custom message.
+    pub fn at(token: &APIToken, message: impl Into<String>) -> Self {
+        PError {
+            span: Some(token.span.clone()),
+            found: Some((token.kind.clone(), token.text().into())),
+            ..PError::new(message)
+        }
+    }
+
+    /// An error for a token the lexer could not recognise.
+    pub fn unexpected_char(token: &APIToken) -> Self {
+        // the lexer rejects a `/*` without its closing `*/`
+        if token.text().starts_with("/*") {
+            return PError::at(token, "unterminated block comment")
+                .with_help("close the comment with `*/`");
+        }
+        let ch = token.text().chars().next().unwrap_or_default();
+        PError::at(token, format!("unexpected character {ch:?}"))
+    }
+
+    /// An error raised at the first token of `input` when `expected` was required there.
+    pub fn expected(input: Input, expected: Expected) -> Self {
+        let mut err = match input.first() {
+            Some(token) if token.kind == APITokenKind::Error => {
+                return PError::unexpected_char(token)
+            }
+            Some(token) => PError::at(token, ""),
+            None => PError::new("").with_help("the file ended before the definition was complete"),
+        };
+        err.expected.push(expected);
+        err
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into().into_boxed_str());
+        self
+    }
+
+    /// Unwraps the error out of a nom result error.
+    pub fn from_nom(err: nom::Err<PError>) -> Self {
+        match err {
+            nom::Err::Error(e) | nom::Err::Failure(e) => e,
+            nom::Err::Incomplete(_) => PError::new("unexpected end of input"),
+        }
+    }
+
+    /// The one line description of the error: `message`, or what was expected
+    /// and found instead when the parser recorded expectations.
+    pub fn summary(&self) -> String {
+        if self.expected.is_empty() {
+            return self.message.clone();
+        }
+
+        let expected = match self.expected.as_slice() {
+            [one] => one.to_string(),
+            many => format!(
+                "one of {}",
+                many.iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+        match &self.found {
+            Some((kind, text)) => format!("expected {expected}, found {kind} `{text}`"),
+            None => format!("expected {expected}, found end of file"),
+        }
+    }
+
+    /// Renders the error against the source it was parsed from, e.g.
+    ///
+    /// ```text
+    /// error: expected CloseBrace, found Identifier `Age`
+    ///   --> example.api:3:5
+    ///    |
+    ///  3 |     Age int
+    ///    |     ^^^
+    ///    = help: ...
+    /// ```
+    pub fn render(&self, file_name: &str, source: &str) -> String {
+        render_diagnostic(
+            file_name,
+            source,
+            self.span.clone(),
+            &self.summary(),
+            self.help.as_deref(),
+        )
+    }
+
+    // byte offset used to decide which of two alternatives got further.
+    fn offset(&self) -> usize {
+        self.span.as_ref().map_or(usize::MAX, |span| span.start)
+    }
+}
+
+impl std::fmt::Display for PError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.span {
+            Some(span) => write!(f, "{} at {}..{}", self.summary(), span.start, span.end),
+            None => write!(f, "{}", self.summary()),
+        }
     }
 }
 
 impl nom::error::ParseError<Input<'_>> for PError {
     fn from_error_kind(input: Input, kind: nom::error::ErrorKind) -> Self {
-        PError(format!("Error: {:?} at {:?}", kind, input))
+        match input.first() {
+            Some(token) => PError::at(
+                token,
+                format!("unexpected {} `{}`", token.kind, token.text()),
+            ),
+            None => PError::new(format!("unexpected end of file ({:?})", kind)),
+        }
     }
 
     fn append(_: Input, _: nom::error::ErrorKind, other: Self) -> Self {
         other
     }
+
+    // Keep the alternative that got furthest, merging the expectations of
+    // alternatives that failed at the same token.
+    fn or(self, other: Self) -> Self {
+        match self.offset().cmp(&other.offset()) {
+            std::cmp::Ordering::Greater => self,
+            std::cmp::Ordering::Less => other,
+            std::cmp::Ordering::Equal if self.expected.is_empty() || other.expected.is_empty() => {
+                other
+            }
+            std::cmp::Ordering::Equal => {
+                let mut merged = self;
+                for expected in other.expected {
+                    if !merged.expected.contains(&expected) {
+                        merged.expected.push(expected);
+                    }
+                }
+                merged.help = merged.help.or(other.help);
+                merged
+            }
+        }
+    }
+}
+
+/// 1-based line and column (in chars) of a byte offset.
+pub fn line_col(source: &str, offset: usize) -> (usize, usize) {
+    let offset = offset.min(source.len());
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, source[line_start..offset].chars().count() + 1)
+}
+
+/// Renders a diagnostic with the source line and a caret under `span`.
+/// A `None` span points just past the last non-whitespace character.
+pub fn render_diagnostic(
+    file_name: &str,
+    source: &str,
+    span: Option<Range<usize>>,
+    message: &str,
+    help: Option<&str>,
+) -> String {
+    let span = span.unwrap_or_else(|| {
+        let end = source.trim_end().len();
+        end..end
+    });
+    let (line, col) = line_col(source, span.start);
+
+    let line_start = source[..span.start.min(source.len())]
+        .rfind('\n')
+        .map_or(0, |i| i + 1);
+    let line_text = source[line_start..]
+        .split('\n')
+        .next()
+        .unwrap_or("")
+        .trim_end_matches('\r');
+
+    // the caret covers the span, clipped to the first line.
+    let width = source
+        .get(span.clone())
+        .map_or(1, |s| s.split('\n').next().unwrap_or("").chars().count())
+        .max(1);
+
+    let gutter = " ".repeat(line.to_string().len());
+    let indent: String = line_text
+        .chars()
+        .take(col - 1)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+
+    let mut out = format!("error: {message}\n");
+    out += &format!("{gutter}--> {file_name}:{line}:{col}\n");
+    out += &format!("{gutter} |\n");
+    out += &format!("{line} | {line_text}\n");
+    out += &format!("{gutter} | {indent}{}\n", "^".repeat(width));
+    if let Some(help) = help {
+        out += &format!("{gutter} = help: {help}\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::tokenize;
+
+    #[test]
+    fn test_line_col() {
+        let source = "type A {\n  Name string\n}";
+        assert_eq!(line_col(source, 0), (1, 1));
+        assert_eq!(line_col(source, 11), (2, 3));
+        assert_eq!(line_col(source, source.len()), (3, 2));
+    }
+
+    #[test]
+    fn test_render_diagnostic() {
+        let source = "type A struct {\n    Name string\n    Age\n}";
+        let tokens = tokenize(source);
+        let age = tokens.iter().find(|t| t.text() == "Age").unwrap();
+
+        let err = PError::expected(&tokens[6..], Expected::Kind(APITokenKind::CloseBrace))
+            .with_help("close the struct with `}`");
+        assert_eq!(err.span, Some(age.span.clone()));
+
+        let rendered = err.render("example.api", source);
+        assert_eq!(
+            rendered,
+            "error: expected CloseBrace, found Identifier `Age`\n \
+             --> example.api:3:5\n  \
+             |\n\
+             3 |     Age\n  \
+             |     ^^^\n  \
+             = help: close the struct with `}`\n"
+        );
+    }
 }
@@ -0,0 +1,127 @@
+#![allow(dead_code)]
+
+use indexmap::IndexMap;
+use nom::combinator::cut;
+use nom::sequence::{preceded, tuple};
+
+use crate::common::{many_till_token, match_text, match_token, unquote, IResult, Input};
+use crate::error::PError;
+use crate::token::APITokenKind::*;
+
+/// The syntax versions this parser understands.
+pub const SUPPORTED_SYNTAX: &[&str] = &["v1"];
+
+/// syntax = "v1"
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxStmt {
+    pub version: String,
+}
+
+/// info (
+///     title: "user api"
+///     desc: "user management"
+///     author: "hanxuanliang"
+///     email: "hanxuanliang@example.com"
+///     version: "1.0"
+/// )
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct InfoStmt {
+    pub title: Option<String>,
+    pub desc: Option<String>,
+    pub author: Option<String>,
+    pub email: Option<String>,
+    pub version: Option<String>,
+    /// Keys other than the well-known ones, in declaration order.
+    pub extra: IndexMap<String, String>,
+}
+
+pub fn parse_syntax(i: Input) -> IResult<SyntaxStmt> {
+    let (i, (_, _, version)) = tuple((
+        match_text("syntax"),
+        cut(match_token(Equals)),
+        cut(match_token(StringLiteral)),
+    ))(i)?;
+
+    let value = unquote(version.text());
+    if !SUPPORTED_SYNTAX.contains(&value.as_str()) {
+        return Err(nom::Err::Failure(
+            PError::at(version, format!("unsupported syntax version \"{value}\"")).with_help(
+                format!("supported versions: {}", SUPPORTED_SYNTAX.join(", ")),
+            ),
+        ));
+    }
+
+    Ok((i, SyntaxStmt { version: value }))
+}
+
+pub fn parse_info(i: Input) -> IResult<InfoStmt> {
+    preceded(
+        tuple((match_text("info"), match_token(OpenParen))),
+        cut(many_till_token(
+            tuple((
+                match_token(Identifier),
+                cut(match_token(Colon)),
+                cut(match_token(StringLiteral)),
+            )),
+            CloseParen,
+        )),
+    )(i)
+    .map(|(i, pairs)| {
+        let mut info = InfoStmt::default();
+        for (key, _, value) in pairs {
+            let value = unquote(value.text());
+            match key.text() {
+                "title" => info.title = Some(value),
+                "desc" => info.desc = Some(value),
+                "author" => info.author = Some(value),
+                "email" => info.email = Some(value),
+                "version" => info.version = Some(value),
+                other => {
+                    info.extra.insert(other.to_string(), value);
+                }
+            }
+        }
+        (i, info)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::tokenize;
+
+    #[test]
+    fn it_parse_syntax() {
+        let tokens = tokenize(r#"syntax = "v1""#);
+        let (_, syntax) = parse_syntax(&tokens).unwrap();
+        assert_eq!(syntax.version, "v1");
+
+        let tokens = tokenize(r#"syntax = "v2""#);
+        let err = PError::from_nom(parse_syntax(&tokens).unwrap_err());
+        assert_eq!(err.summary(), "unsupported syntax version \"v2\"");
+        assert_eq!(err.span, Some(9..13));
+    }
+
+    #[test]
+    fn it_parse_info() {
+        let source = r#"
+        info (
+            title: "user api"
+            desc: "user \"management\""
+            author: "hanxuanliang"
+            version: "1.0"
+            go: "1.21"
+        )
+        "#;
+        let tokens = tokenize(source);
+        let (rest, info) = parse_info(&tokens).unwrap();
+
+        assert!(rest.is_empty());
+        assert_eq!(info.title.as_deref(), Some("user api"));
+        assert_eq!(info.desc.as_deref(), Some("user \"management\""));
+        assert_eq!(info.author.as_deref(), Some("hanxuanliang"));
+        assert_eq!(info.email, None);
+        assert_eq!(info.version.as_deref(), Some("1.0"));
+        assert_eq!(info.extra.get("go").map(String::as_str), Some("1.21"));
+    }
+}
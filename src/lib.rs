@@ -1,10 +1,14 @@
-pub mod parser;
 pub mod cli;
+pub mod loader;
+pub mod parser;
 
+mod analysis;
 mod common;
 mod error;
+mod info;
 mod service;
 mod struct_ref;
+mod tag;
 mod token;
 
 mod openapi;
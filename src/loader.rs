@@ -0,0 +1,338 @@
+#![allow(dead_code)]
+
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use nom::branch::alt;
+use nom::combinator::{cut, map};
+use nom::sequence::preceded;
+use thiserror::Error;
+
+use crate::common::{many_till_token, match_text, match_token, unquote, IResult, Input, Origin};
+use crate::error::render_diagnostic;
+use crate::parser::{mismatched_service, parse_api_file, APIStmt};
+use crate::struct_ref::Field;
+use crate::token::{tokenize, APIToken, APITokenKind::*};
+
+/// import "user.api"
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportStmt {
+    pub path: String,
+    pub span: Range<usize>,
+}
+
+/// A file read while loading an API project.
+#[derive(Debug)]
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub source: String,
+}
+
+/// An API merged from a root file and everything it imports.
+///
+/// The `Origin::file` of every definition indexes `files`, the root file is 0.
+#[derive(Debug)]
+pub struct ApiProject {
+    pub files: Vec<SourceFile>,
+    pub api: APIStmt,
+}
+
+impl ApiProject {
+    /// Renders a diagnostic pointing at `origin` in the file it belongs to.
+    pub fn render(&self, origin: &Origin, message: &str, help: Option<&str>) -> String {
+        let file = &self.files[origin.file];
+        render_diagnostic(
+            &file.path.display().to_string(),
+            &file.source,
+            Some(origin.span.clone()),
+            message,
+            help,
+        )
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum LoadError {
+    #[error("Failed to read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    /// A rendered diagnostic pointing into one of the files.
+    #[error("{0}")]
+    Parse(String),
+
+    #[error("{rendered}")]
+    Cycle {
+        cycle: Vec<PathBuf>,
+        rendered: String,
+    },
+}
+
+// import "a.api"
+// import (
+//     "a.api"
+//     "b.api"
+// )
+pub fn parse_import(i: Input) -> IResult<Vec<ImportStmt>> {
+    preceded(
+        match_text("import"),
+        cut(alt((
+            map(match_token(StringLiteral), |path| vec![import_stmt(path)]),
+            preceded(
+                match_token(OpenParen),
+                many_till_token(map(match_token(StringLiteral), import_stmt), CloseParen),
+            ),
+        ))),
+    )(i)
+}
+
+fn import_stmt(path: &APIToken) -> ImportStmt {
+    ImportStmt {
+        path: unquote(path.text()),
+        span: path.span.clone(),
+    }
+}
+
+/// Loads the API file at `path` together with its imports, resolved relative
+/// to the importing file. Each file is merged once, an import cycle is an error.
+pub fn load_api(path: &Path) -> Result<ApiProject, LoadError> {
+    let mut loader = Loader {
+        files: vec![],
+        loaded: vec![],
+        stack: vec![],
+    };
+    let api = loader.load(path, None)?;
+
+    Ok(ApiProject {
+        files: loader.files,
+        api,
+    })
+}
+
+struct Loader {
+    files: Vec<SourceFile>,
+    // canonical paths of `files`, by index
+    loaded: Vec<PathBuf>,
+    // canonical paths of the files being loaded, importers first
+    stack: Vec<PathBuf>,
+}
+
+impl Loader {
+    // `importer` is the file and span of the import statement that led here.
+    fn load(
+        &mut self,
+        path: &Path,
+        importer: Option<(usize, Range<usize>)>,
+    ) -> Result<APIStmt, LoadError> {
+        let canonical = path.canonicalize().map_err(|e| match &importer {
+            Some((file, span)) => self.diagnostic(
+                *file,
+                span.clone(),
+                &format!("cannot read imported file {}: {e}", path.display()),
+                None,
+            ),
+            None => LoadError::Io {
+                path: path.to_path_buf(),
+                source: e,
+            },
+        })?;
+
+        let source = fs::read_to_string(&canonical).map_err(|source| LoadError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        let file = self.files.len();
+        self.files.push(SourceFile {
+            path: path.to_path_buf(),
+            source,
+        });
+        self.loaded.push(canonical.clone());
+        self.stack.push(canonical);
+
+        let tokens = tokenize(&self.files[file].source);
+        let mut api = parse_api_file(&tokens).map_err(|e| {
+            LoadError::Parse(e.render(&self.display_path(file), &self.files[file].source))
+        })?;
+        set_file(&mut api, file);
+
+        let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        for import in api.imports.clone() {
+            let import_path = dir.join(&import.path);
+            let import_canonical = import_path.canonicalize().ok();
+
+            if let Some(at) = import_canonical
+                .as_ref()
+                .and_then(|c| self.stack.iter().position(|p| p == c))
+            {
+                let mut cycle = self.stack[at..].to_vec();
+                cycle.push(self.stack[at].clone());
+                let help = cycle
+                    .iter()
+                    .map(|p| p.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+                let rendered = render_diagnostic(
+                    &self.display_path(file),
+                    &self.files[file].source,
+                    Some(import.span.clone()),
+                    &format!("import cycle through \"{}\"", import.path),
+                    Some(&format!("the files import each other: {help}")),
+                );
+                return Err(LoadError::Cycle { cycle, rendered });
+            }
+            if import_canonical.is_some_and(|c| self.loaded.contains(&c)) {
+                continue; // already merged through another import
+            }
+
+            let imported = self.load(&import_path, Some((file, import.span.clone())))?;
+            merge(&mut api, imported).map_err(|(message, origin)| {
+                self.diagnostic(origin.file, origin.span, &message, None)
+            })?;
+        }
+
+        self.stack.pop();
+        Ok(api)
+    }
+
+    fn display_path(&self, file: usize) -> String {
+        self.files[file].path.display().to_string()
+    }
+
+    fn diagnostic(
+        &self,
+        file: usize,
+        span: Range<usize>,
+        message: &str,
+        help: Option<&str>,
+    ) -> LoadError {
+        LoadError::Parse(render_diagnostic(
+            &self.display_path(file),
+            &self.files[file].source,
+            Some(span),
+            message,
+            help,
+        ))
+    }
+}
+
+fn set_file(api: &mut APIStmt, file: usize) {
+    for struct_def in api.type_struct.iter_mut() {
+        struct_def.origin.file = file;
+        set_fields_file(&mut struct_def.fields, file);
+    }
+    for alias in api.type_alias.iter_mut() {
+        alias.origin.file = file;
+        if let Some(inline) = alias.target.inline_fields_mut() {
+            set_fields_file(inline, file);
+        }
+    }
+    for service in api.services.iter_mut() {
+        service.origin.file = file;
+        for handler in service.handlers.iter_mut() {
+            handler.origin.file = file;
+            for origin in [&mut handler.req_origin, &mut handler.resp_origin]
+                .into_iter()
+                .flatten()
+            {
+                origin.file = file;
+            }
+        }
+    }
+}
+
+fn set_fields_file(fields: &mut [Field], file: usize) {
+    for field in fields.iter_mut() {
+        field.origin.file = file;
+        field.type_origin.file = file;
+        if let Some(inline) = field.field_type.inline_fields_mut() {
+            set_fields_file(inline, file);
+        }
+    }
+}
+
+// Merges an imported API into its importer. Imported syntax and info are
+// dropped, the importer's describe the project.
+fn merge(api: &mut APIStmt, imported: APIStmt) -> Result<(), (String, Origin)> {
+    api.type_struct.extend(imported.type_struct);
+    api.type_alias.extend(imported.type_alias);
+    api.services.extend(imported.services);
+
+    match mismatched_service(&api.services) {
+        Some(service) => Err((
+            format!(
+                "service `{}` does not match service `{}` declared by the importing file",
+                service.name, api.services[0].name
+            ),
+            service.origin.clone(),
+        )),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parse_import() {
+        let source = r#"
+        import "user.api"
+        import (
+            "a.api"
+            "shared/b.api"
+        )
+        "#;
+        let tokens = tokenize(source);
+        let (rest, imports) = nom::multi::many0(parse_import)(&tokens).unwrap();
+
+        assert!(rest.is_empty());
+        let paths = imports
+            .into_iter()
+            .flatten()
+            .map(|i| i.path)
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["user.api", "a.api", "shared/b.api"]);
+    }
+
+    #[test]
+    fn it_load_api() {
+        let project = load_api(Path::new("tests/imports/main.api")).unwrap();
+
+        let names = project
+            .api
+            .type_struct
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["GetUserReq", "User", "Status"]);
+        assert_eq!(project.files.len(), 3);
+
+        let status = &project.api.type_struct[2];
+        assert_eq!(
+            project.files[status.origin.file].path,
+            Path::new("tests/imports/shared/status.api")
+        );
+        assert_eq!(project.api.services[0].handlers.len(), 1);
+    }
+
+    #[test]
+    fn it_load_api_import_cycle() {
+        let err = load_api(Path::new("tests/imports/cycle/a.api")).unwrap_err();
+
+        match err {
+            LoadError::Cycle { cycle, rendered } => {
+                let names = cycle
+                    .iter()
+                    .map(|p| p.file_name().unwrap().to_str().unwrap())
+                    .collect::<Vec<_>>();
+                assert_eq!(names, vec!["a.api", "b.api", "a.api"]);
+                assert!(rendered.contains("tests/imports/cycle/b.api:3:8"));
+            }
+            other => panic!("expected an import cycle, got {other:?}"),
+        }
+    }
+}
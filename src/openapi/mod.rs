@@ -1 +1 @@
-pub mod swagger;
\ No newline at end of file
+pub mod swagger;
@@ -1,28 +1,93 @@
 #![allow(dead_code)]
 
-use serde::Serialize;
+use indexmap::IndexMap;
 use serde_json::{json, Value};
+use thiserror::Error;
 
-use crate::{parser::APIStmt, struct_ref::FieldType};
+use crate::{
+    analysis::{ResolvedApi, SymbolTable},
+    common::Origin,
+    info::InfoStmt,
+    service::{HttpMethod, Route, ServerAnnotation},
+    struct_ref::{Field, FieldType, StructDef},
+};
 
 #[derive(Default)]
 pub struct Swagger {
+    version: OpenApiVersion,
+    info: Value,
     paths: Value,
     definitions: Value,
+    security_schemes: Value,
 }
 
-#[derive(Serialize)]
-struct SwaggerField {
-    #[serde(flatten)]
-    field_type: FieldType,
-    description: String,
+/// The OpenAPI version of the generated document. Schemas are built for 3.0
+/// and rewritten for 3.1, where `null` is a type and exclusive bounds are numbers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, clap::ValueEnum)]
+pub enum OpenApiVersion {
+    #[default]
+    #[value(name = "3.0")]
+    V3_0,
+    #[value(name = "3.1")]
+    V3_1,
 }
 
+/// Options of the Swagger generator, `SwaggerOptions::default()` is what `to_swagger` uses.
+#[derive(Debug, Clone, Default)]
+pub struct SwaggerOptions {
+    /// Name of the bearer JWT security scheme of `@server(jwt: ...)` blocks.
+    /// Defaults to the `jwt` value, e.g. `Auth`.
+    pub jwt_scheme: Option<String>,
+    /// Name of the API key security scheme of `@server(signature: true)` blocks.
+    /// Defaults to `signature`.
+    pub signature_scheme: Option<String>,
+    /// Inline the fields of embedded structs into their parent's schema instead
+    /// of composing the parent with `allOf`.
+    pub flatten_embeds: bool,
+    pub openapi_version: OpenApiVersion,
+}
+
+// The tags go-zero binds request fields by, besides `json`.
+const BINDING_TAGS: [&str; 3] = ["form", "path", "header"];
+
+// go-zero reads request signatures from this header.
+const SIGNATURE_HEADER: &str = "X-Content-Security";
+
 impl Swagger {
     fn new() -> Swagger {
         Swagger {
+            version: OpenApiVersion::V3_0,
+            info: json!({
+                "version": "1.0.0",
+                "title": "Generated Swagger API"
+            }),
             paths: json!({}),
             definitions: json!({}),
+            security_schemes: json!({}),
+        }
+    }
+
+    fn set_info(&mut self, info: &InfoStmt) {
+        let info_entry = self.info.as_object_mut().unwrap();
+        if let Some(title) = &info.title {
+            info_entry.insert("title".to_string(), json!(title));
+        }
+        if let Some(version) = &info.version {
+            info_entry.insert("version".to_string(), json!(version));
+        }
+        if let Some(desc) = &info.desc {
+            info_entry.insert("description".to_string(), json!(desc));
+        }
+
+        let mut contact = serde_json::Map::new();
+        if let Some(author) = &info.author {
+            contact.insert("name".to_string(), json!(author));
+        }
+        if let Some(email) = &info.email {
+            contact.insert("email".to_string(), json!(email));
+        }
+        if !contact.is_empty() {
+            info_entry.insert("contact".to_string(), Value::Object(contact));
         }
     }
 
@@ -41,84 +106,682 @@ impl Swagger {
         def_entry.insert(name.to_string(), def);
     }
 
+    fn append_security_scheme(&mut self, name: &str, scheme: Value) {
+        let scheme_entry = self.security_schemes.as_object_mut().unwrap();
+        scheme_entry.insert(name.to_string(), scheme);
+    }
+
     fn to_json(&self) -> Value {
-        json!({
-            "openapi": "3.0.3",
-            "info": {
-                "version": "1.0.0",
-                "title": "Generated Swagger API"
-            },
+        let openapi = match self.version {
+            OpenApiVersion::V3_0 => "3.0.3",
+            OpenApiVersion::V3_1 => "3.1.0",
+        };
+        let mut swagger = json!({
+            "openapi": openapi,
+            "info": self.info,
             "paths": self.paths,
             "components": {
                 "schemas": self.definitions,
             },
-        })
+        });
+        if self
+            .security_schemes
+            .as_object()
+            .is_some_and(|s| !s.is_empty())
+        {
+            swagger["components"]["securitySchemes"] = self.security_schemes.clone();
+        }
+        if self.version == OpenApiVersion::V3_1 {
+            upgrade_schemas(&mut swagger);
+        }
+        swagger
     }
+}
 
-    pub fn to_string(&self) -> String {
-        serde_json::to_string_pretty(&self.to_json()).unwrap()
+impl std::fmt::Display for Swagger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            serde_json::to_string_pretty(&self.to_json()).unwrap()
+        )
     }
 }
 
-pub fn to_swagger(api_data: APIStmt) -> Swagger {
+#[derive(Debug, Error)]
+pub enum SwaggerError {
+    #[error(
+        "route {path} has parameter `{param}`, but no request field is tagged `path:\"{param}\"`"
+    )]
+    MissingPathParam {
+        path: String,
+        param: String,
+        origin: Origin,
+    },
+    #[error("field `{field}` has invalid tag option `{option}`: {reason}")]
+    InvalidTagOption {
+        field: String,
+        option: String,
+        reason: String,
+        origin: Origin,
+    },
+}
+
+impl SwaggerError {
+    /// Where the offending definition was declared.
+    pub fn origin(&self) -> &Origin {
+        match self {
+            SwaggerError::MissingPathParam { origin, .. } => origin,
+            SwaggerError::InvalidTagOption { origin, .. } => origin,
+        }
+    }
+}
+
+/// Generates the document of an analyzed API, see `analysis::analyze`.
+pub fn to_swagger(resolved: &ResolvedApi) -> Result<Swagger, SwaggerError> {
+    to_swagger_with(resolved, &SwaggerOptions::default())
+}
+
+pub fn to_swagger_with(
+    resolved: &ResolvedApi,
+    options: &SwaggerOptions,
+) -> Result<Swagger, SwaggerError> {
+    let ResolvedApi {
+        api: api_data,
+        types,
+    } = resolved;
     let mut swagger = Swagger::new();
+    swagger.version = options.openapi_version;
 
-    for struct_def in api_data.type_struct {
-        let struct_name = struct_def.name;
-        let props = struct_def
-            .fields
-            .iter()
-            .map(|field| {
-                (
-                    field.name.to_lowercase(),
-                    serde_json::to_value(&SwaggerField {
-                        field_type: field.field_type.clone(),
-                        description: field.name.to_string(),
-                    })
-                    .unwrap_or(json!({})),
-                )
-            })
-            .collect::<serde_json::Map<_, _>>();
+    if let Some(info) = &api_data.info {
+        swagger.set_info(info);
+    }
+
+    for struct_def in &api_data.type_struct {
+        let schema = component_schema(types, struct_def, options)?;
+        swagger.append_def(&struct_def.name, schema);
+    }
+    for alias in &api_data.type_alias {
+        swagger.append_def(&alias.name, type_schema(types, &alias.target)?);
+    }
+
+    for service in &api_data.services {
+        let security = security_requirement(&mut swagger, &service.annotation, options);
+
+        for route in service.routes() {
+            append_route(&mut swagger, types, &route, &security)?;
+        }
+    }
+
+    Ok(swagger)
+}
+
+// security_requirement registers the security schemes a service block uses
+// and returns the requirement of its operations, `null` when unprotected.
+fn security_requirement(
+    swagger: &mut Swagger,
+    annotation: &ServerAnnotation,
+    options: &SwaggerOptions,
+) -> Value {
+    let mut requirement = serde_json::Map::new();
 
-        swagger.append_def(
-            &struct_name,
+    if let Some(jwt) = &annotation.jwt {
+        let name = options.jwt_scheme.as_deref().unwrap_or(jwt);
+        swagger.append_security_scheme(
+            name,
+            json!({
+                "type": "http",
+                "scheme": "bearer",
+                "bearerFormat": "JWT",
+            }),
+        );
+        requirement.insert(name.to_string(), json!([]));
+    }
+    if annotation.signature == Some(true) {
+        let name = options.signature_scheme.as_deref().unwrap_or("signature");
+        swagger.append_security_scheme(
+            name,
             json!({
-                "type": "object",
-                "properties": props,
+                "type": "apiKey",
+                "in": "header",
+                "name": SIGNATURE_HEADER,
             }),
         );
+        requirement.insert(name.to_string(), json!([]));
     }
 
-    for handler in api_data.service.handlers {
-        let operation = json!({
-            "summary": handler.name,
-            "responses": {
-                "200": match &handler.resp_type {
-                    Some(resp_type) => {
-                        json!({
-                            "description": "successful operation",
-                            "content": {
-                                "application/json": {
-                                    "schema": {
-                                        "$ref": format!("#/components/schemas/{}", resp_type),
-                                    },
+    match requirement.is_empty() {
+        true => Value::Null,
+        false => json!([requirement]),
+    }
+}
+
+fn append_route(
+    swagger: &mut Swagger,
+    types: &SymbolTable,
+    route: &Route,
+    security: &Value,
+) -> Result<(), SwaggerError> {
+    let handler = route.handler;
+    // go-zero binds the fields promoted from embedded structs like any other
+    let req = handler
+        .req_type
+        .as_deref()
+        .and_then(|name| types.struct_named(name))
+        .map(|req| (req, promoted_fields(types, req).0));
+    let mut parameters =
+        path_parameters(types, route, req.as_ref().map(|(_, fields)| &fields[..]))?;
+    let mut request_body = None;
+    if let Some((req, fields)) = &req {
+        if !handler.method.has_form_body() {
+            parameters.extend(tagged_parameters(types, fields, "form", "query")?);
+        }
+        parameters.extend(tagged_parameters(types, fields, "header", "header")?);
+        request_body = request_body_of(types, handler.method, req, fields)?;
+    }
+
+    let mut operation = json!({
+        "summary": handler.name,
+        "responses": {
+            "200": match &handler.resp_type {
+                Some(resp_type) => {
+                    json!({
+                        "description": "successful operation",
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "$ref": format!("#/components/schemas/{}", resp_type),
                                 },
                             },
-                        })
-                    }
-                    None => json!({"description": "OK"}),
-                },
+                        },
+                    })
+                }
+                None => json!({"description": "OK"}),
             },
-        });
+        },
+    });
+    if !parameters.is_empty() {
+        operation["parameters"] = Value::Array(parameters);
+    }
+    if let Some(request_body) = request_body {
+        operation["requestBody"] = request_body;
+    }
+    if !security.is_null() {
+        operation["security"] = security.clone();
+    }
+
+    swagger.append_path(
+        &route.openapi_path(),
+        &handler.method.to_string(),
+        operation,
+    );
+
+    Ok(())
+}
+
+// path_parameters builds an `in: path` parameter for each `:param` of the route
+// from the request field tagged `path:"param"`.
+fn path_parameters(
+    types: &SymbolTable,
+    route: &Route,
+    req: Option<&[&Field]>,
+) -> Result<Vec<Value>, SwaggerError> {
+    route
+        .path_params()
+        .map(|param| {
+            let field = req
+                .and_then(|fields| fields.iter().find(|f| f.bound_name("path") == Some(param)))
+                .ok_or_else(|| SwaggerError::MissingPathParam {
+                    path: route.path(),
+                    param: param.to_string(),
+                    origin: route.handler.origin.clone(),
+                })?;
+
+            Ok(json!({
+                "name": param,
+                "in": "path",
+                "required": true,
+                "schema": property_schema(types, field, "path")?,
+            }))
+        })
+        .collect()
+}
+
+// tagged_parameters builds a parameter `in` the given location for each
+// request field with a `tag` tag, e.g. `form:"page"` as the `page` query parameter.
+fn tagged_parameters(
+    types: &SymbolTable,
+    fields: &[&Field],
+    tag: &str,
+    location: &str,
+) -> Result<Vec<Value>, SwaggerError> {
+    fields
+        .iter()
+        .filter_map(|field| field.bound_name(tag).map(|name| (name, field)))
+        .map(|(name, field)| {
+            let mut parameter = json!({
+                "name": name,
+                "in": location,
+                "schema": property_schema(types, field, tag)?,
+            });
+            if field.is_required(tag) {
+                parameter["required"] = json!(true);
+            }
+            Ok(parameter)
+        })
+        .collect()
+}
+
+// request_body_of builds the body of a request the way go-zero's httpx binds it:
+// `json` fields (and untagged ones) from a JSON body, `form` fields from an
+// urlencoded body for the methods that have one.
+fn request_body_of(
+    types: &SymbolTable,
+    method: HttpMethod,
+    req: &StructDef,
+    fields: &[&Field],
+) -> Result<Option<Value>, SwaggerError> {
+    let mut content = serde_json::Map::new();
+
+    let json_fields = fields
+        .iter()
+        .filter(|field| is_json_field(field))
+        .collect::<Vec<_>>();
+    if !json_fields.is_empty() && !matches!(method, HttpMethod::GET | HttpMethod::HEAD) {
+        // a struct bound from the body alone is described by its component
+        let schema = match json_fields.len() == fields.len() {
+            true => json!({ "$ref": format!("#/components/schemas/{}", req.name) }),
+            false => object_schema(
+                types,
+                json_fields.iter().map(|field| {
+                    let name = field.bound_name("json").unwrap_or(&field.name);
+                    (name, **field, "json")
+                }),
+            )?,
+        };
+        content.insert("application/json".to_string(), json!({ "schema": schema }));
+    }
+
+    if method.has_form_body() {
+        let form_fields = fields
+            .iter()
+            .filter_map(|field| field.bound_name("form").map(|name| (name, *field, "form")))
+            .collect::<Vec<_>>();
+        if !form_fields.is_empty() {
+            content.insert(
+                "application/x-www-form-urlencoded".to_string(),
+                json!({ "schema": object_schema(types, form_fields.into_iter())? }),
+            );
+        }
+    }
+
+    Ok((!content.is_empty()).then(|| json!({ "content": content })))
+}
+
+// component_schema describes a struct. Its embedded structs are referenced
+// through `allOf`, or their fields flattened into its own properties with
+// `flatten_embeds`. `allOf` cannot express a field shadowing a promoted one, so
+// structs with shadowed fields are always flattened.
+fn component_schema(
+    types: &SymbolTable,
+    def: &StructDef,
+    options: &SwaggerOptions,
+) -> Result<Value, SwaggerError> {
+    let (promoted, shadowed) = promoted_fields(types, def);
+    let embeds = def
+        .fields
+        .iter()
+        .filter_map(|field| expanded_embed(field))
+        .collect::<Vec<_>>();
+    if options.flatten_embeds || shadowed || embeds.is_empty() {
+        return fields_schema(types, promoted);
+    }
+
+    let own = def
+        .fields
+        .iter()
+        .filter(|field| expanded_embed(field).is_none())
+        .collect::<Vec<_>>();
+    let mut all_of = embeds
+        .iter()
+        .map(|name| json!({ "$ref": format!("#/components/schemas/{name}") }))
+        .collect::<Vec<_>>();
+    if !own.is_empty() {
+        all_of.push(fields_schema(types, own)?);
+    }
+    Ok(json!({ "allOf": all_of }))
+}
+
+// promoted_fields lists the fields of `def` as Go sees them: embedded structs are
+// replaced by their fields, transitively, and a field hides those of the same
+// name from deeper embeds. Fields clashing at the same depth are dropped, as
+// `encoding/json` does. The flag tells whether any field was hidden or dropped.
+fn promoted_fields<'a>(types: &SymbolTable<'a>, def: &'a StructDef) -> (Vec<&'a Field>, bool) {
+    let mut found = vec![];
+    collect_fields(types, def, 0, &mut vec![def.name.as_str()], &mut found);
+
+    let mut by_name = IndexMap::<&str, Vec<(usize, &Field)>>::new();
+    for (depth, field) in found {
+        let name = property_name(field).unwrap_or(&field.name);
+        by_name.entry(name).or_default().push((depth, field));
+    }
+
+    let shadowed = by_name.values().any(|fields| fields.len() > 1);
+    let fields = by_name
+        .into_values()
+        .filter_map(|fields| {
+            let depth = fields.iter().map(|(depth, _)| *depth).min()?;
+            let mut dominant = fields.into_iter().filter(|(d, _)| *d == depth);
+            match (dominant.next(), dominant.next()) {
+                (Some((_, field)), None) => Some(field),
+                _ => None,
+            }
+        })
+        .collect();
+    (fields, shadowed)
+}
+
+// collect_fields gathers the fields of `def` with their embedding depth. `visiting`
+// holds the structs being expanded, an embed cycle contributes no fields.
+fn collect_fields<'a>(
+    types: &SymbolTable<'a>,
+    def: &'a StructDef,
+    depth: usize,
+    visiting: &mut Vec<&'a str>,
+    found: &mut Vec<(usize, &'a Field)>,
+) {
+    for field in &def.fields {
+        let Some(name) = expanded_embed(field) else {
+            found.push((depth, field));
+            continue;
+        };
+        // unknown structs are left to the reference they are rendered as
+        let Some(embedded) = types.struct_named(name) else {
+            continue;
+        };
+        if visiting.contains(&embedded.name.as_str()) {
+            continue;
+        }
+        visiting.push(&embedded.name);
+        collect_fields(types, embedded, depth + 1, visiting, found);
+        visiting.pop();
+    }
+}
 
-        swagger.append_path(&handler.path, &handler.method.to_string(), operation)
+// expanded_embed is the struct an embedded field promotes fields from. An embed
+// named by a tag, as in `Status `json:"status"``, is an ordinary field instead.
+fn expanded_embed(field: &Field) -> Option<&str> {
+    field.embedded().filter(|_| binding_tag(field).is_none())
+}
+
+// A field is bound from a JSON body when tagged `json` (but not `json:"-"`), or
+// when it has no binding tag at all, as `encoding/json` falls back to the field name.
+fn is_json_field(field: &Field) -> bool {
+    match field.tag_value("json") {
+        Some(name) => name != "-",
+        None => BINDING_TAGS
+            .iter()
+            .all(|tag| field.tag_value(tag).is_none()),
+    }
+}
+
+// binding_tag is the tag a field is described by in its component schema, json
+// first, `None` for an untagged field.
+fn binding_tag(field: &Field) -> Option<&'static str> {
+    std::iter::once("json")
+        .chain(BINDING_TAGS)
+        .find(|tag| field.tag_value(tag).is_some())
+}
+
+// property_name names a field in its component schema after the tag it is bound
+// by, so that the schema matches the wire format.
+fn property_name(field: &Field) -> Option<&str> {
+    match binding_tag(field) {
+        Some(tag) => field.bound_name(tag),
+        None => Some(&field.name),
+    }
+}
+
+// fields_schema describes the fields of a struct as an object, each under its
+// binding tag, or `json` for body fields.
+fn fields_schema<'a>(
+    types: &SymbolTable,
+    fields: impl IntoIterator<Item = &'a Field>,
+) -> Result<Value, SwaggerError> {
+    let fields = fields.into_iter().filter_map(|field| {
+        let tag = binding_tag(field).unwrap_or("json");
+        property_name(field).map(|name| (name, field, tag))
+    });
+    object_schema(types, fields)
+}
+
+// object_schema builds an object schema from `(property name, field, tag)`
+// triples, `tag` being the one the field is bound by.
+fn object_schema<'a>(
+    types: &SymbolTable,
+    fields: impl Iterator<Item = (&'a str, &'a Field, &'a str)>,
+) -> Result<Value, SwaggerError> {
+    let mut props = serde_json::Map::new();
+    let mut required = vec![];
+    for (name, field, tag) in fields {
+        let mut schema = property_schema(types, field, tag)?;
+        schema["description"] = json!(field.name);
+        props.insert(name.to_string(), schema);
+        if field.is_required(tag) {
+            required.push(name);
+        }
+    }
+
+    let mut schema = json!({
+        "type": "object",
+        "properties": props,
+    });
+    if !required.is_empty() {
+        schema["required"] = json!(required);
+    }
+    Ok(schema)
+}
+
+// property_schema describes the type of a field along with the
+// validation options of its `tag` entry: `options=` as `enum`, `range=` as
+// `minimum`/`maximum` and `default=` as `default`, values checked against the type.
+fn property_schema(types: &SymbolTable, field: &Field, tag: &str) -> Result<Value, SwaggerError> {
+    let mut schema = type_schema(types, &field.field_type)?;
+    let Some(entry) = field.tag.get(tag) else {
+        return Ok(schema);
+    };
+    let invalid = |option: String, reason: String| SwaggerError::InvalidTagOption {
+        field: field.name.clone(),
+        option,
+        reason,
+        origin: Origin {
+            file: field.origin.file,
+            span: entry.span.clone(),
+        },
+    };
+    // options constrain the value behind pointers and aliases
+    let value_type = match types.resolve(&field.field_type) {
+        FieldType::Pointer(inner) => types.resolve(inner),
+        resolved => resolved,
+    };
+    let literal = |option: &str, value: &str| {
+        value_type.literal(value).ok_or_else(|| {
+            invalid(
+                format!("{option}={value}"),
+                format!("`{value}` is not a valid {}", field.field_type.describe()),
+            )
+        })
+    };
+
+    if let Some(options) = entry.enum_options() {
+        let values = options
+            .iter()
+            .map(|value| literal("options", value))
+            .collect::<Result<Vec<_>, _>>()?;
+        schema["enum"] = json!(values);
+    }
+    if let Some(default) = entry.default() {
+        schema["default"] = literal("default", default)?;
+    }
+    if let Some(range) = entry.range() {
+        if !value_type.is_numeric() {
+            return Err(invalid(
+                "range".to_string(),
+                format!("{} is not numeric", field.field_type.describe()),
+            ));
+        }
+        if let Some(min) = range.min {
+            schema["minimum"] = number(min);
+            if !range.min_inclusive {
+                schema["exclusiveMinimum"] = json!(true);
+            }
+        }
+        if let Some(max) = range.max {
+            schema["maximum"] = number(max);
+            if !range.max_inclusive {
+                schema["exclusiveMaximum"] = json!(true);
+            }
+        }
+    }
+
+    // the siblings of a `$ref` are ignored, constrain the referenced type through `allOf`
+    if let Some(schema) = schema.as_object_mut().filter(|schema| schema.len() > 1) {
+        if let Some(reference) = schema.remove("$ref") {
+            schema.insert("allOf".to_string(), json!([{ "$ref": reference }]));
+        }
+    }
+
+    Ok(schema)
+}
+
+// type_schema describes a field type. Anonymous structs, wherever they are
+// nested, become inline object schemas of their fields.
+fn type_schema(types: &SymbolTable, field_type: &FieldType) -> Result<Value, SwaggerError> {
+    let mut schema = serde_json::to_value(field_type).unwrap_or(json!({}));
+    match field_type {
+        FieldType::Inline(fields) => {
+            schema = fields_schema(types, fields)?;
+        }
+        // `[]byte` is a string and has no items
+        FieldType::Array(ele_type) | FieldType::FixedArray(_, ele_type)
+            if schema.get("items").is_some() =>
+        {
+            schema["items"] = type_schema(types, ele_type)?;
+        }
+        FieldType::Map(_, value_type) => {
+            schema["additionalProperties"] = type_schema(types, value_type)?;
+        }
+        FieldType::Pointer(inner) if !matches!(**inner, FieldType::StructRef { .. }) => {
+            schema = type_schema(types, inner)?;
+            schema["nullable"] = json!(true);
+        }
+        _ => {}
+    }
+    Ok(schema)
+}
+
+// number keeps whole bounds integral, `range=[1:10]` reads `minimum: 1` rather than `1.0`.
+fn number(value: f64) -> Value {
+    match value.fract() == 0.0 && value.abs() < i64::MAX as f64 {
+        true => json!(value as i64),
+        false => json!(value),
+    }
+}
+
+// upgrade_schemas rewrites every schema of an OpenAPI 3.0 document for 3.1: the
+// components, and the schemas of parameters, request bodies and responses.
+fn upgrade_schemas(document: &mut Value) {
+    if let Some(schemas) = document
+        .pointer_mut("/components/schemas")
+        .and_then(Value::as_object_mut)
+    {
+        schemas.values_mut().for_each(upgrade_schema);
+    }
+
+    let Some(paths) = document.get_mut("paths").and_then(Value::as_object_mut) else {
+        return;
+    };
+    let operations = paths
+        .values_mut()
+        .filter_map(Value::as_object_mut)
+        .flat_map(|path| path.values_mut());
+    for operation in operations {
+        if let Some(parameters) = operation
+            .get_mut("parameters")
+            .and_then(Value::as_array_mut)
+        {
+            for parameter in parameters {
+                parameter.get_mut("schema").map(upgrade_schema);
+            }
+        }
+        if let Some(body) = operation.get_mut("requestBody") {
+            upgrade_content(body);
+        }
+        if let Some(responses) = operation
+            .get_mut("responses")
+            .and_then(Value::as_object_mut)
+        {
+            responses.values_mut().for_each(upgrade_content);
+        }
     }
+}
 
-    swagger
+// upgrade_content upgrades the schema of each media type of a request body or response.
+fn upgrade_content(body: &mut Value) {
+    if let Some(content) = body.get_mut("content").and_then(Value::as_object_mut) {
+        for media in content.values_mut() {
+            media.get_mut("schema").map(upgrade_schema);
+        }
+    }
+}
+
+// upgrade_schema rewrites the OpenAPI 3.0 keywords of `schema` and its subschemas
+// for 3.1: `nullable` becomes a `null` type, or an `anyOf` alternative for
+// references, and boolean exclusive bounds take the value of the bound. Only
+// schema positions are visited, so properties may be named like the keywords.
+fn upgrade_schema(schema: &mut Value) {
+    let Some(schema) = schema.as_object_mut() else {
+        return;
+    };
+    if schema.get("nullable") == Some(&Value::Bool(true)) {
+        schema.remove("nullable");
+        if let Some(Value::String(ty)) = schema.get("type") {
+            let ty = ty.clone();
+            schema.insert("type".to_string(), json!([ty, "null"]));
+        } else if let Some(Value::Array(all_of)) = schema.remove("allOf") {
+            let mut any_of = all_of;
+            any_of.push(json!({ "type": "null" }));
+            schema.insert("anyOf".to_string(), json!(any_of));
+        }
+    }
+    for (exclusive, bound) in [
+        ("exclusiveMinimum", "minimum"),
+        ("exclusiveMaximum", "maximum"),
+    ] {
+        if schema.get(exclusive) == Some(&Value::Bool(true)) {
+            schema.remove(exclusive);
+            if let Some(bound) = schema.remove(bound) {
+                schema.insert(exclusive.to_string(), bound);
+            }
+        }
+    }
+
+    if let Some(properties) = schema.get_mut("properties").and_then(Value::as_object_mut) {
+        properties.values_mut().for_each(upgrade_schema);
+    }
+    for key in ["items", "additionalProperties"] {
+        schema.get_mut(key).map(upgrade_schema);
+    }
+    for key in ["allOf", "anyOf", "oneOf"] {
+        if let Some(schemas) = schema.get_mut(key).and_then(Value::as_array_mut) {
+            schemas.iter_mut().for_each(upgrade_schema);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::analysis::analyze;
     use crate::parser::parse_api;
     use crate::token::tokenize;
 
@@ -128,6 +791,15 @@ mod tests {
     // cargo test --package goctl-rs --lib -- openapi::swagger::tests::it_to_swagger --exact --nocapture
     fn it_to_swagger() {
         let source = r#"
+        syntax = "v1"
+
+        info (
+            title: "example"
+            desc: "form and json examples"
+            author: "hanxuanliang"
+            version: "0.1.0"
+        )
+
         type (
             PostFormReq struct {
                 Name    string   `form:"name"`
@@ -158,13 +830,741 @@ mod tests {
 
             @handler postJson
             post /example/json (PostFormReq) returns (PostFormResp)
+
+            @handler putJson
+            put /example/json (PostFormReq) returns (PostFormResp)
+
+            @handler deleteForm
+            delete /example/form (GetFormReq)
         }
         "#;
         let input = tokenize(source);
         let result = parse_api(&input);
 
         let api_data = result.unwrap().1;
-        let swagger = to_swagger(api_data);
-        println!("{}", swagger.to_string());
+        let swagger = to_swagger(&analyze(&api_data).unwrap()).unwrap();
+        println!("{}", swagger);
+
+        let info = &swagger.to_json()["info"];
+        assert_eq!(info["title"], "example");
+        assert_eq!(info["description"], "form and json examples");
+        assert_eq!(info["version"], "0.1.0");
+        assert_eq!(info["contact"]["name"], "hanxuanliang");
+
+        let paths = &swagger.to_json()["paths"];
+        assert_eq!(paths["/example/form"]["get"]["summary"], "getForm");
+        assert_eq!(paths["/example/form"]["delete"]["summary"], "deleteForm");
+        assert_eq!(paths["/example/json"]["post"]["summary"], "postJson");
+        assert_eq!(paths["/example/json"]["put"]["summary"], "putJson");
+    }
+
+    #[test]
+    fn it_to_swagger_path_params() {
+        let source = r#"
+        type GetUserReq struct {
+            ID   int64  `path:"id"`
+            Name string `form:"name"`
+        }
+        type User struct {
+            Name string `json:"name"`
+        }
+        @server (
+            prefix: /api/v1
+        )
+        service user {
+            @handler getUser
+            get /users/:id (GetUserReq) returns (User)
+        }
+        "#;
+        let input = tokenize(source);
+        let api_data = parse_api(&input).unwrap().1;
+        let swagger = to_swagger(&analyze(&api_data).unwrap()).unwrap().to_json();
+
+        let operation = &swagger["paths"]["/api/v1/users/{id}"]["get"];
+        assert_eq!(
+            operation["parameters"],
+            json!([{
+                "name": "id",
+                "in": "path",
+                "required": true,
+                "schema": {"type": "integer", "format": "int64"},
+            }, {
+                "name": "name",
+                "in": "query",
+                "required": true,
+                "schema": {"type": "string"},
+            }])
+        );
+    }
+
+    #[test]
+    fn it_to_swagger_missing_path_param() {
+        let source = r#"
+        type GetUserReq struct {
+            Name string `form:"name"`
+        }
+        service user {
+            @handler getUser
+            get /users/:id (GetUserReq)
+        }
+        "#;
+        let input = tokenize(source);
+        let api_data = parse_api(&input).unwrap().1;
+
+        let err = to_swagger(&analyze(&api_data).unwrap()).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "route /users/:id has parameter `id`, but no request field is tagged `path:\"id\"`"
+        );
+    }
+
+    #[test]
+    fn it_to_swagger_security() {
+        let source = r#"
+        type User struct {
+            Name string `json:"name"`
+        }
+        @server (
+            jwt: Auth
+            signature: true
+        )
+        service user {
+            @handler getUser
+            get /user returns (User)
+        }
+        service user {
+            @handler ping
+            get /ping
+        }
+        "#;
+        let input = tokenize(source);
+        let api_data = parse_api(&input).unwrap().1;
+
+        let swagger = to_swagger(&analyze(&api_data).unwrap()).unwrap().to_json();
+        assert_eq!(
+            swagger["components"]["securitySchemes"],
+            json!({
+                "Auth": {"type": "http", "scheme": "bearer", "bearerFormat": "JWT"},
+                "signature": {"type": "apiKey", "in": "header", "name": "X-Content-Security"},
+            })
+        );
+        assert_eq!(
+            swagger["paths"]["/user"]["get"]["security"],
+            json!([{"Auth": [], "signature": []}])
+        );
+        assert!(swagger["paths"]["/ping"]["get"].get("security").is_none());
+
+        let options = SwaggerOptions {
+            jwt_scheme: Some("bearerAuth".to_string()),
+            signature_scheme: Some("apiSignature".to_string()),
+            ..Default::default()
+        };
+        let swagger = to_swagger_with(&analyze(&api_data).unwrap(), &options)
+            .unwrap()
+            .to_json();
+        assert_eq!(
+            swagger["paths"]["/user"]["get"]["security"],
+            json!([{"bearerAuth": [], "apiSignature": []}])
+        );
+    }
+
+    #[test]
+    fn it_to_swagger_request() {
+        let source = r#"
+        type UpdateUserReq struct {
+            ID      int64    `path:"id"`
+            Token   string   `header:"X-Token"`
+            Page    int      `form:"page"`
+            Name    string   `json:"name"`
+            Hobbits []string `json:"hobbits"`
+        }
+        type CreateUserReq struct {
+            Name string `json:"name"`
+            Age  int
+        }
+        type SearchReq struct {
+            Keyword string `form:"keyword"`
+        }
+        service user {
+            @handler updateUser
+            put /users/:id (UpdateUserReq)
+
+            @handler createUser
+            post /users (CreateUserReq)
+
+            @handler search
+            get /search (SearchReq)
+
+            @handler postSearch
+            post /search (SearchReq)
+        }
+        "#;
+        let input = tokenize(source);
+        let api_data = parse_api(&input).unwrap().1;
+        let swagger = to_swagger(&analyze(&api_data).unwrap()).unwrap().to_json();
+
+        let update = &swagger["paths"]["/users/{id}"]["put"];
+        let params = update["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| (p["name"].as_str().unwrap(), p["in"].as_str().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(params, vec![("id", "path"), ("X-Token", "header")]);
+        let body = &update["requestBody"]["content"];
+        assert_eq!(
+            body["application/json"]["schema"]["properties"]
+                .as_object()
+                .unwrap()
+                .keys()
+                .collect::<Vec<_>>(),
+            vec!["hobbits", "name"]
+        );
+        assert_eq!(
+            body["application/x-www-form-urlencoded"]["schema"]["properties"]["page"]["type"],
+            "integer"
+        );
+
+        let create = &swagger["paths"]["/users"]["post"];
+        assert_eq!(
+            create["requestBody"]["content"]["application/json"]["schema"],
+            json!({"$ref": "#/components/schemas/CreateUserReq"})
+        );
+
+        let search = &swagger["paths"]["/search"]["get"];
+        assert_eq!(
+            search["parameters"],
+            json!([{
+                "name": "keyword",
+                "in": "query",
+                "required": true,
+                "schema": {"type": "string"},
+            }])
+        );
+        assert!(search.get("requestBody").is_none());
+
+        let post_search = &swagger["paths"]["/search"]["post"];
+        assert!(post_search.get("parameters").is_none());
+        assert!(post_search["requestBody"]["content"]
+            .get("application/x-www-form-urlencoded")
+            .is_some());
+    }
+
+    #[test]
+    fn it_to_swagger_property_names() {
+        let source = r#"
+        type User struct {
+            UserID   int64  `json:"user_id"`
+            Nickname string `json:",optional"`
+            Password string `json:"-"`
+            Avatar   string
+        }
+        type ListReq struct {
+            PageSize int    `form:"page_size"`
+            Secret   string `form:"-"`
+            TraceID  string `header:"X-Trace-Id"`
+        }
+        service user {
+            @handler list
+            get /users (ListReq) returns (User)
+        }
+        "#;
+        let input = tokenize(source);
+        let api_data = parse_api(&input).unwrap().1;
+        let swagger = to_swagger(&analyze(&api_data).unwrap()).unwrap().to_json();
+
+        let props = swagger["components"]["schemas"]["User"]["properties"]
+            .as_object()
+            .unwrap();
+        assert_eq!(
+            props.keys().collect::<Vec<_>>(),
+            vec!["Avatar", "Nickname", "user_id"]
+        );
+
+        let params = swagger["paths"]["/users"]["get"]["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(params, vec!["page_size", "X-Trace-Id"]);
+    }
+
+    #[test]
+    fn it_to_swagger_required() {
+        let source = r#"
+        type User struct {
+            Name     string `json:"name"`
+            Nickname string `json:"nickname,optional"`
+            Avatar   string `json:"avatar,omitempty"`
+            Gender   string `json:"gender,default=unknown"`
+            Status
+        }
+        type Status struct {
+            Code int `json:"code"`
+        }
+        type ListReq struct {
+            Page    int    `form:"page,default=1"`
+            Keyword string `form:"keyword"`
+            TraceID string `header:"X-Trace-Id,optional"`
+        }
+        type UpdateReq struct {
+            ID   int64  `path:"id"`
+            Name string `json:"name"`
+            Bio  string `json:"bio,optional"`
+        }
+        service user {
+            @handler list
+            get /users (ListReq) returns (User)
+
+            @handler update
+            put /users/:id (UpdateReq)
+        }
+        "#;
+        let input = tokenize(source);
+        let api_data = parse_api(&input).unwrap().1;
+        let swagger = to_swagger(&analyze(&api_data).unwrap()).unwrap().to_json();
+
+        assert_eq!(
+            swagger["components"]["schemas"]["User"]["allOf"][1]["required"],
+            json!(["name"])
+        );
+
+        let required = swagger["paths"]["/users"]["get"]["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| (p["name"].as_str().unwrap(), p.get("required").is_some()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            required,
+            vec![("page", false), ("keyword", true), ("X-Trace-Id", false)]
+        );
+
+        let body = &swagger["paths"]["/users/{id}"]["put"]["requestBody"];
+        assert_eq!(
+            body["content"]["application/json"]["schema"]["required"],
+            json!(["name"])
+        );
+    }
+
+    #[test]
+    fn it_to_swagger_validation() {
+        let source = r#"
+        type ListReq struct {
+            Page   int     `form:"page,default=1,range=[1:]"`
+            Size   int32   `form:"size,range=(0:100]"`
+            Gender string  `form:"gender,options=male|female,default=male"`
+            Active bool    `form:"active,default=true"`
+        }
+        service user {
+            @handler list
+            get /users (ListReq)
+        }
+        "#;
+        let input = tokenize(source);
+        let api_data = parse_api(&input).unwrap().1;
+        let swagger = to_swagger(&analyze(&api_data).unwrap()).unwrap().to_json();
+
+        let props = &swagger["components"]["schemas"]["ListReq"]["properties"];
+        assert_eq!(props["page"]["default"], json!(1));
+        assert_eq!(props["page"]["minimum"], json!(1));
+        assert!(props["page"].get("maximum").is_none());
+        assert_eq!(props["size"]["minimum"], json!(0));
+        assert_eq!(props["size"]["exclusiveMinimum"], json!(true));
+        assert_eq!(props["size"]["maximum"], json!(100));
+        assert!(props["size"].get("exclusiveMaximum").is_none());
+        assert_eq!(props["gender"]["enum"], json!(["male", "female"]));
+        assert_eq!(props["gender"]["default"], json!("male"));
+        assert_eq!(props["active"]["default"], json!(true));
+
+        let params = &swagger["paths"]["/users"]["get"]["parameters"];
+        assert_eq!(params[0]["schema"]["default"], json!(1));
+    }
+
+    #[test]
+    fn it_to_swagger_invalid_default() {
+        let source = r#"
+        type ListReq struct {
+            Page int `form:"page,default=abc"`
+        }
+        "#;
+        let input = tokenize(source);
+        let api_data = parse_api(&input).unwrap().1;
+        let err = match to_swagger(&analyze(&api_data).unwrap()) {
+            Err(err) => err,
+            Ok(_) => panic!("expected an invalid default error"),
+        };
+
+        assert_eq!(
+            err.to_string(),
+            "field `Page` has invalid tag option `default=abc`: `abc` is not a valid int"
+        );
+        assert_eq!(
+            &source[err.origin().span.clone()],
+            r#"form:"page,default=abc""#
+        );
+
+        let source = r#"
+        type ListReq struct {
+            Name string `form:"name,range=[1:10]"`
+        }
+        "#;
+        let input = tokenize(source);
+        let api_data = parse_api(&input).unwrap().1;
+        assert!(matches!(
+            to_swagger(&analyze(&api_data).unwrap()),
+            Err(SwaggerError::InvalidTagOption { .. })
+        ));
+    }
+
+    #[test]
+    fn it_to_swagger_embeds() {
+        let source = r#"
+        type Base struct {
+            ID int64 `json:"id"`
+        }
+        type Status struct {
+            Base
+            Code int    `json:"code"`
+            Msg  string `json:"msg"`
+        }
+        type Resp struct {
+            Status
+            Total int64 `json:"total"`
+        }
+        type Named struct {
+            Status `json:"status"`
+        }
+        "#;
+        let input = tokenize(source);
+        let api_data = parse_api(&input).unwrap().1;
+
+        let swagger = to_swagger(&analyze(&api_data).unwrap()).unwrap().to_json();
+        let schemas = &swagger["components"]["schemas"];
+        assert_eq!(
+            schemas["Resp"]["allOf"][0],
+            json!({"$ref": "#/components/schemas/Status"})
+        );
+        assert_eq!(schemas["Resp"]["allOf"][1]["required"], json!(["total"]));
+        assert_eq!(
+            schemas["Status"]["allOf"][0],
+            json!({"$ref": "#/components/schemas/Base"})
+        );
+        assert_eq!(
+            schemas["Named"]["properties"]["status"]["$ref"],
+            "#/components/schemas/Status"
+        );
+
+        let options = SwaggerOptions {
+            flatten_embeds: true,
+            ..Default::default()
+        };
+        let swagger = to_swagger_with(&analyze(&api_data).unwrap(), &options)
+            .unwrap()
+            .to_json();
+        let resp = &swagger["components"]["schemas"]["Resp"];
+        assert_eq!(
+            resp["properties"]
+                .as_object()
+                .unwrap()
+                .keys()
+                .collect::<Vec<_>>(),
+            vec!["code", "id", "msg", "total"]
+        );
+        assert_eq!(resp["required"], json!(["id", "code", "msg", "total"]));
+    }
+
+    #[test]
+    fn it_to_swagger_embed_shadowing() {
+        let source = r#"
+        type Audit struct {
+            Name string `json:"name"`
+        }
+        type Owner struct {
+            Name string `json:"name"`
+        }
+        type Base struct {
+            Code int    `json:"code"`
+            Msg  string `json:"msg"`
+        }
+        type Resp struct {
+            Base
+            Msg int `json:"msg"`
+        }
+        type Both struct {
+            Audit
+            Owner
+            ID int64 `json:"id"`
+        }
+        type GetReq struct {
+            Base
+            ID int64 `path:"id"`
+        }
+        service user {
+            @handler getUser
+            post /users/:id (GetReq)
+        }
+        "#;
+        let input = tokenize(source);
+        let api_data = parse_api(&input).unwrap().1;
+        let swagger = to_swagger(&analyze(&api_data).unwrap()).unwrap().to_json();
+        let schemas = &swagger["components"]["schemas"];
+
+        // the outer `Msg` hides the promoted one, which allOf cannot express
+        assert!(schemas["Resp"].get("allOf").is_none());
+        assert_eq!(schemas["Resp"]["properties"]["msg"]["type"], "integer");
+        assert_eq!(schemas["Resp"]["properties"]["code"]["type"], "integer");
+
+        // `name` is ambiguous at the same depth and dropped
+        assert_eq!(
+            schemas["Both"]["properties"]
+                .as_object()
+                .unwrap()
+                .keys()
+                .collect::<Vec<_>>(),
+            vec!["id"]
+        );
+
+        // promoted fields end up in the request body
+        let body = &swagger["paths"]["/users/{id}"]["post"]["requestBody"]["content"];
+        assert_eq!(
+            body["application/json"]["schema"]["required"],
+            json!(["code", "msg"])
+        );
+    }
+
+    #[test]
+    fn it_to_swagger_pointers() {
+        let source = r#"
+        type Profile struct {
+            Bio string `json:"bio"`
+        }
+        type User struct {
+            Name    string   `json:"name"`
+            Age     *int     `json:"age,range=(0:150]"`
+            Profile *Profile `json:"profile"`
+            Tags    [2]string `json:"tags"`
+        }
+        "#;
+        let input = tokenize(source);
+        let api_data = parse_api(&input).unwrap().1;
+
+        let swagger = to_swagger(&analyze(&api_data).unwrap()).unwrap().to_json();
+        let user = &swagger["components"]["schemas"]["User"];
+        assert_eq!(user["required"], json!(["name", "tags"]));
+        assert_eq!(user["properties"]["age"]["nullable"], json!(true));
+        assert_eq!(user["properties"]["age"]["exclusiveMinimum"], json!(true));
+        assert_eq!(
+            user["properties"]["profile"]["allOf"],
+            json!([{"$ref": "#/components/schemas/Profile"}])
+        );
+        assert_eq!(user["properties"]["tags"]["maxItems"], json!(2));
+
+        let options = SwaggerOptions {
+            openapi_version: OpenApiVersion::V3_1,
+            ..Default::default()
+        };
+        let swagger = to_swagger_with(&analyze(&api_data).unwrap(), &options)
+            .unwrap()
+            .to_json();
+        assert_eq!(swagger["openapi"], "3.1.0");
+        let user = &swagger["components"]["schemas"]["User"];
+        let age = &user["properties"]["age"];
+        assert_eq!(age["type"], json!(["integer", "null"]));
+        assert!(age.get("nullable").is_none());
+        assert!(age.get("minimum").is_none());
+        assert_eq!(age["exclusiveMinimum"], json!(0));
+        assert_eq!(age["maximum"], json!(150));
+        assert_eq!(
+            user["properties"]["profile"]["anyOf"],
+            json!([{"$ref": "#/components/schemas/Profile"}, {"type": "null"}])
+        );
+    }
+
+    #[test]
+    fn it_to_swagger_inline_struct() {
+        let source = r#"
+        type User struct {
+            Address struct {
+                City string `json:"city"`
+                Zip  string `json:"zip,optional"`
+            } `json:"address"`
+            Tags []struct {
+                Name string `json:"name,default=new"`
+            } `json:"tags"`
+        }
+        "#;
+        let input = tokenize(source);
+        let api_data = parse_api(&input).unwrap().1;
+        let swagger = to_swagger(&analyze(&api_data).unwrap()).unwrap().to_json();
+
+        let props = &swagger["components"]["schemas"]["User"]["properties"];
+        assert_eq!(props["address"]["type"], "object");
+        assert_eq!(props["address"]["properties"]["city"]["type"], "string");
+        assert_eq!(props["address"]["required"], json!(["city"]));
+        assert_eq!(props["tags"]["type"], "array");
+        assert_eq!(
+            props["tags"]["items"]["properties"]["name"]["default"],
+            json!("new")
+        );
+    }
+
+    #[test]
+    fn it_to_swagger_struct_aliases() {
+        let source = r#"
+        type Base struct {
+            ID   int64  `path:"id"`
+            Name string `json:"name"`
+        }
+        type Req = Base
+        type B2 = Base
+        type EmbedReq struct {
+            B2
+            Note string `json:"note,optional"`
+        }
+        service user {
+            @handler updateA
+            put /a/:id (Req)
+
+            @handler updateB
+            put /b/:id (EmbedReq)
+        }
+        "#;
+        let input = tokenize(source);
+        let api_data = parse_api(&input).unwrap().1;
+        let resolved = analyze(&api_data).unwrap();
+        let swagger = to_swagger(&resolved).unwrap().to_json();
+
+        for path in ["/a/{id}", "/b/{id}"] {
+            let operation = &swagger["paths"][path]["put"];
+            assert_eq!(operation["parameters"][0]["name"], "id", "{path}");
+            let body = &operation["requestBody"]["content"]["application/json"]["schema"];
+            assert_eq!(body["properties"]["name"]["type"], "string", "{path}");
+        }
+        assert_eq!(
+            swagger["components"]["schemas"]["EmbedReq"]["allOf"][0],
+            json!({"$ref": "#/components/schemas/B2"})
+        );
+
+        let options = SwaggerOptions {
+            flatten_embeds: true,
+            ..Default::default()
+        };
+        let swagger = to_swagger_with(&resolved, &options).unwrap().to_json();
+        let props = &swagger["components"]["schemas"]["EmbedReq"]["properties"];
+        assert_eq!(
+            props.as_object().unwrap().keys().collect::<Vec<_>>(),
+            vec!["id", "name", "note"]
+        );
+    }
+
+    #[test]
+    fn it_to_swagger_type_alias() {
+        let source = r#"
+        type UserID int64
+        type Tags = []string
+        type Age UserID
+        type GetUserReq struct {
+            ID   UserID `path:"id,range=[1:]"`
+            Age  *Age   `form:"age,default=18"`
+            Tags Tags   `form:"tags,optional"`
+        }
+        service user {
+            @handler getUser
+            get /users/:id (GetUserReq)
+        }
+        "#;
+        let input = tokenize(source);
+        let api_data = parse_api(&input).unwrap().1;
+        assert_eq!(api_data.type_alias.len(), 3);
+        let swagger = to_swagger(&analyze(&api_data).unwrap()).unwrap().to_json();
+
+        let schemas = &swagger["components"]["schemas"];
+        assert_eq!(
+            schemas["UserID"],
+            json!({"type": "integer", "format": "int64"})
+        );
+        assert_eq!(schemas["Tags"]["items"]["type"], "string");
+        assert_eq!(
+            schemas["Age"],
+            json!({"$ref": "#/components/schemas/UserID"})
+        );
+
+        let params = &swagger["paths"]["/users/{id}"]["get"]["parameters"];
+        assert_eq!(
+            params[0]["schema"],
+            json!({"allOf": [{"$ref": "#/components/schemas/UserID"}], "minimum": 1})
+        );
+        assert_eq!(params[1]["schema"]["default"], json!(18));
+        assert_eq!(
+            params[2]["schema"],
+            json!({"$ref": "#/components/schemas/Tags"})
+        );
+    }
+
+    #[test]
+    fn it_to_swagger_recursive_types() {
+        let source = r#"
+        type Node struct {
+            Value    int             `json:"value"`
+            Children []Node          `json:"children"`
+            Parent   *Node           `json:"parent,optional"`
+            Index    map[string]Node `json:"index"`
+        }
+        service tree {
+            @handler getTree
+            get /tree returns (Node)
+        }
+        "#;
+        let input = tokenize(source);
+        let api_data = parse_api(&input).unwrap().1;
+        let swagger = to_swagger(&analyze(&api_data).unwrap()).unwrap().to_json();
+
+        let properties = &swagger["components"]["schemas"]["Node"]["properties"];
+        let node = json!({"$ref": "#/components/schemas/Node"});
+        assert_eq!(properties["children"]["items"], node);
+        assert_eq!(properties["parent"]["allOf"][0], node);
+        assert_eq!(properties["index"]["additionalProperties"], node);
+    }
+
+    #[test]
+    fn it_to_swagger_keyword_property_names() {
+        let source = r#"
+        type Bounds struct {
+            Nullable         bool    `json:"nullable"`
+            ExclusiveMinimum bool    `json:"exclusiveMinimum"`
+            ExclusiveMaximum float64 `json:"exclusiveMaximum,range=(0:1)"`
+            Items            []*int  `json:"items"`
+        }
+        type BoundsReq struct {
+            Ratio *float64 `form:"ratio,range=(0:1]"`
+        }
+        service bounds {
+            @handler getBounds
+            get /bounds (BoundsReq) returns (Bounds)
+        }
+        "#;
+        let input = tokenize(source);
+        let api_data = parse_api(&input).unwrap().1;
+        let options = SwaggerOptions {
+            openapi_version: OpenApiVersion::V3_1,
+            ..Default::default()
+        };
+        let swagger = to_swagger_with(&analyze(&api_data).unwrap(), &options)
+            .unwrap()
+            .to_json();
+
+        let props = &swagger["components"]["schemas"]["Bounds"]["properties"];
+        assert_eq!(props["nullable"]["type"], "boolean");
+        assert_eq!(props["exclusiveMinimum"]["type"], "boolean");
+        assert_eq!(props["exclusiveMaximum"]["exclusiveMinimum"], json!(0));
+        assert_eq!(props["exclusiveMaximum"]["exclusiveMaximum"], json!(1));
+        assert_eq!(props["items"]["items"]["type"], json!(["integer", "null"]));
+
+        let ratio = &swagger["paths"]["/bounds"]["get"]["parameters"][0]["schema"];
+        assert_eq!(ratio["type"], json!(["number", "null"]));
+        assert_eq!(ratio["exclusiveMinimum"], json!(0));
+        assert_eq!(ratio["maximum"], json!(1));
     }
 }
@@ -1,40 +1,132 @@
 #![allow(dead_code)]
 
+use nom::branch::alt;
+use nom::combinator::{map, opt};
+use nom::multi::many0;
 use nom::sequence::tuple;
 
 use crate::{
     common::{IResult, Input},
+    error::PError,
+    info::{parse_info, parse_syntax, InfoStmt, SyntaxStmt},
+    loader::{parse_import, ImportStmt},
     service::{parse_service, Service},
-    struct_ref::{parse_struct_stmt1, StructDef},
+    struct_ref::{parse_type_stmt, StructDef, TypeAlias, TypeDef},
+    token::APITokenKind,
 };
 
 #[derive(Debug)]
 pub struct APIStmt {
+    pub syntax: Option<SyntaxStmt>,
+    pub info: Option<InfoStmt>,
+    pub imports: Vec<ImportStmt>,
     pub type_struct: Vec<StructDef>,
-    pub service: Service,
+    /// Named non-struct types, `type UserID int64`.
+    pub type_alias: Vec<TypeAlias>,
+    /// The blocks of the API's service, in declaration order. Files that only
+    /// declare types, like most imported ones, have none.
+    pub services: Vec<Service>,
+}
+
+impl APIStmt {
+    pub fn service_name(&self) -> Option<&str> {
+        self.services.first().map(|s| s.name.as_str())
+    }
+}
+
+// A top level declaration, types and service blocks can be interleaved.
+enum Decl {
+    Types(Vec<TypeDef>),
+    Service(Box<Service>),
 }
 
 pub fn parse_api(i: Input) -> IResult<APIStmt> {
-    tuple((parse_struct_stmt1, parse_service))(i).map(|(i, (type_struct, service))| {
-        (
-            i,
-            APIStmt {
-                type_struct,
-                service,
-            },
-        )
+    tuple((
+        opt(parse_syntax),
+        opt(parse_info),
+        many0(parse_import),
+        many0(alt((
+            map(parse_type_stmt, Decl::Types),
+            map(parse_service, |s| Decl::Service(Box::new(s))),
+        ))),
+    ))(i)
+    .map(|(i, (syntax, info, imports, decls))| {
+        let mut api = APIStmt {
+            syntax,
+            info,
+            imports: imports.into_iter().flatten().collect(),
+            type_struct: vec![],
+            type_alias: vec![],
+            services: vec![],
+        };
+        for decl in decls {
+            match decl {
+                Decl::Types(types) => {
+                    for type_def in types {
+                        match type_def {
+                            TypeDef::Struct(struct_def) => api.type_struct.push(struct_def),
+                            TypeDef::Alias(alias) => api.type_alias.push(alias),
+                        }
+                    }
+                }
+                Decl::Service(service) => api.services.push(*service),
+            }
+        }
+        (i, api)
     })
 }
 
+/// The first service block whose name differs from the first block's,
+/// every block has to belong to the same service.
+pub fn mismatched_service(services: &[Service]) -> Option<&Service> {
+    let first = services.first()?;
+    services.iter().find(|s| s.name != first.name)
+}
+
+/// Parses a whole API file, every token has to be part of the API.
+pub fn parse_api_file(i: Input) -> Result<APIStmt, PError> {
+    if let Some(token) = i.iter().find(|token| token.kind == APITokenKind::Error) {
+        return Err(PError::unexpected_char(token));
+    }
+
+    let (rest, api) = parse_api(i).map_err(PError::from_nom)?;
+    if let Some(service) = mismatched_service(&api.services) {
+        return Err(PError {
+            span: Some(service.origin.span.clone()),
+            ..PError::new(format!(
+                "service `{}` does not match service `{}` declared before",
+                service.name, api.services[0].name
+            ))
+            .with_help("every service block of an API must use the same service name")
+        });
+    }
+
+    match rest.first() {
+        None => Ok(api),
+        Some(token) => Err(PError::at(
+            token,
+            format!("unexpected {} `{}`", token.kind, token.text()),
+        )
+        .with_help("parsing stopped here, nothing from this point on is part of the API")),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::token::tokenize;
+    use crate::{error::line_col, service::HttpMethod, token::tokenize};
 
     #[test]
     // cargo test --package goctl-rs --lib -- parser::tests::test_parse_api --exact --nocapture
     fn test_parse_api() {
         let source = r#"
+        syntax = "v1"
+
+        info (
+            title: "example api"
+            version: "0.1.0"
+        )
+
         type (
             PostFormReq struct {
                 Name    string   `form:"name"`
@@ -56,6 +148,12 @@ mod tests {
             Total int64 `json:"total"`
             Status
         }
+        type PostJsonReq struct {
+            Name string `json:"name"`
+        }
+        type PostJsonResp struct {
+            Status
+        }
 
         @server (
             group:   json
@@ -75,5 +173,223 @@ mod tests {
         let result = parse_api(&input);
         let api_var = result.unwrap().1;
         println!("{:#?}", api_var);
+
+        assert_eq!(api_var.syntax.unwrap().version, "v1");
+        assert_eq!(api_var.info.unwrap().title.as_deref(), Some("example api"));
+    }
+
+    #[test]
+    fn test_parse_api_file_rejects_trailing_input() {
+        let source = r#"
+        type Status struct {
+            Code int `json:"code"`
+        }
+        service example {
+            @handler getStatus
+            get /status returns (Status)
+        }
+        @handler getCode
+        get /code returns (Status)
+        "#;
+        let input = tokenize(source);
+
+        let err = parse_api_file(&input).unwrap_err();
+        let (line, col) = line_col(source, err.span.clone().unwrap().start);
+        assert_eq!((line, col), (9, 9));
+        assert_eq!(err.summary(), "unexpected Handler `@handler`");
+    }
+
+    #[test]
+    fn test_parse_api_file_reports_malformed_struct() {
+        let source = r#"
+        type Status struct {
+            Code int `json:"code"`
+            Msg  map string
+        }
+        service example {
+        }
+        "#;
+        let input = tokenize(source);
+
+        let err = parse_api_file(&input).unwrap_err();
+        assert_eq!(
+            err.summary(),
+            "expected OpenBracket, found StringDataType `string`"
+        );
+    }
+
+    #[test]
+    fn test_parse_api_file_rejects_misspelled_keyword() {
+        let source = r#"
+        type Status struct {
+            Code int `json:"code"`
+        }
+        servce example {
+        }
+        "#;
+        let input = tokenize(source);
+
+        let err = parse_api_file(&input).unwrap_err();
+        assert_eq!(line_col(source, err.span.clone().unwrap().start), (5, 9));
+        assert_eq!(err.summary(), "unexpected Identifier `servce`");
+    }
+
+    #[test]
+    fn test_parse_api_file_reports_malformed_handler() {
+        let source = r#"
+        service example {
+            @handler getStatus
+            get returns (Status)
+        }
+        "#;
+        let input = tokenize(source);
+
+        let err = parse_api_file(&input).unwrap_err();
+        assert_eq!(
+            err.summary(),
+            "expected RoutePath, found RespReturns `returns`"
+        );
+    }
+
+    #[test]
+    fn test_parse_api_file_rejects_unbalanced_type_refs() {
+        let cases = [
+            (
+                "post /a A returns A",
+                "expected one of CloseBrace, Handler, found Identifier `A`",
+            ),
+            (
+                "post /a (A returns A",
+                "expected CloseParen, found RespReturns `returns`",
+            ),
+            (
+                "post /a returns A)",
+                "expected OpenParen, found Identifier `A`",
+            ),
+            (
+                "post /a (A) returns",
+                "expected OpenParen, found CloseBrace `}`",
+            ),
+        ];
+        for (route, expected) in cases {
+            let source = format!("service example {{\n@handler postA\n{route}\n}}");
+            let input = tokenize(&source);
+            let err = parse_api_file(&input).unwrap_err();
+            assert_eq!(err.summary(), expected, "{route}");
+        }
+
+        let source = "service example {\n@handler postA\npost /a (A) returns (B)\n}";
+        let input = tokenize(source);
+        let handler = &parse_api_file(&input).unwrap().services[0].handlers[0];
+        assert_eq!(handler.req_type.as_deref(), Some("A"));
+        assert_eq!(handler.resp_type.as_deref(), Some("B"));
+    }
+
+    #[test]
+    fn test_parse_api_file_reports_lexer_error() {
+        let source = r#"
+        service example {
+            @handler getUserInfo
+            get /user?info returns (UserInfo)
+        }
+        "#;
+        let input = tokenize(source);
+
+        let err = parse_api_file(&input).unwrap_err();
+        assert_eq!(err.summary(), "unexpected character '?'");
+        assert_eq!(line_col(source, err.span.clone().unwrap().start), (4, 22));
+    }
+
+    #[test]
+    fn test_parse_api_file_reports_unterminated_comment() {
+        let source = r#"
+        type Status struct {
+            Code int `json:"code"` /* the result
+        }
+        "#;
+        let input = tokenize(source);
+
+        let err = parse_api_file(&input).unwrap_err();
+        assert_eq!(err.summary(), "unterminated block comment");
+        assert_eq!(line_col(source, err.span.clone().unwrap().start), (3, 36));
+    }
+
+    #[test]
+    fn test_parse_api_many_services() {
+        let source = r#"
+        type GetUserReq struct {
+            ID int64 `path:"id"`
+        }
+        @server (
+            group: user
+        )
+        service example {
+            @handler getUser
+            get /users/:id (GetUserReq)
+        }
+
+        type GetOrderReq struct {
+            ID int64 `path:"id"`
+        }
+        @server (
+            group: order
+        )
+        service example {
+            @handler getOrder
+            get /orders/:id (GetOrderReq)
+        }
+        "#;
+        let input = tokenize(source);
+        let api = parse_api_file(&input).unwrap();
+
+        assert_eq!(api.type_struct.len(), 2);
+        assert_eq!(api.services.len(), 2);
+        assert_eq!(api.service_name(), Some("example"));
+        assert_eq!(api.services[1].handlers[0].name, "getOrder");
+    }
+
+    #[test]
+    fn test_parse_api_method_names_as_identifiers() {
+        let source = r#"
+        type patch struct {
+            head    string   `json:"head"`
+            options []string `json:"options"`
+        }
+        type delete struct {
+            trace patch `json:"trace"`
+        }
+        service example {
+            @handler get
+            patch /example (patch) returns (delete)
+        }
+        "#;
+        let input = tokenize(source);
+        let api = parse_api_file(&input).unwrap();
+
+        assert_eq!(api.type_struct[0].name, "patch");
+        assert_eq!(api.type_struct[0].fields[1].name, "options");
+        assert_eq!(api.type_struct[1].fields[0].name, "trace");
+        let handler = &api.services[0].handlers[0];
+        assert_eq!(handler.name, "get");
+        assert_eq!(handler.method, HttpMethod::PATCH);
+        assert_eq!(handler.req_type.as_deref(), Some("patch"));
+    }
+
+    #[test]
+    fn test_parse_api_service_name_mismatch() {
+        let source = r#"
+        service user {
+        }
+        service order {
+        }
+        "#;
+        let input = tokenize(source);
+
+        let err = parse_api_file(&input).unwrap_err();
+        assert_eq!(
+            err.summary(),
+            "service `order` does not match service `user` declared before"
+        );
+        assert_eq!(line_col(source, err.span.unwrap().start), (4, 17));
     }
 }
@@ -1,41 +1,276 @@
 #![allow(dead_code)]
 
+use std::time::Duration;
+
 use indexmap::IndexMap;
-use nom::combinator::opt;
-use nom::multi::many0;
-use nom::sequence::delimited;
+use nom::combinator::{cut, opt};
+use nom::multi::{many0, separated_list1};
+use nom::sequence::{delimited, preceded, terminated};
 use nom::{branch::alt, combinator::map, sequence::tuple};
 
-use crate::common::{match_text_case_insensitive, match_token, IResult, Input};
-use crate::token::APITokenKind::*;
+use crate::common::{
+    consumed, docs_of, many_till_token, match_text_case_insensitive, match_token, unquote, IResult,
+    Input, Origin,
+};
+use crate::error::{Expected, PError};
+use crate::token::{APIToken, APITokenKind, APITokenKind::*};
 
 #[derive(Debug, Default)]
 pub struct Service {
-    name: String,
-    anotation: Option<IndexMap<String, String>>,
+    pub name: String,
+    pub origin: Origin,
+    /// The `@server` annotation of the block, empty when there is none.
+    pub annotation: ServerAnnotation,
     pub handlers: Vec<Handler>,
 }
 
 #[derive(Debug)]
 pub struct Handler {
+    pub docs: Vec<String>,
     pub name: String,
+    pub origin: Origin,
     pub method: HttpMethod,
     pub path: String,
-    req_type: Option<String>,
+    pub route: Vec<RouteSegment>,
+    pub req_type: Option<String>,
     pub resp_type: Option<String>,
+    /// Where `req_type` and `resp_type` are written.
+    pub req_origin: Option<Origin>,
+    pub resp_origin: Option<Origin>,
+}
+
+/// @server (
+///     group:      user
+///     prefix:     /api/v1
+///     jwt:        Auth
+///     middleware: Log, Cors
+///     timeout:    3s
+///     maxBytes:   1048576
+///     signature:  true
+/// )
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ServerAnnotation {
+    pub group: Option<String>,
+    pub prefix: Option<String>,
+    pub jwt: Option<String>,
+    pub middleware: Vec<String>,
+    pub timeout: Option<Duration>,
+    pub max_bytes: Option<u64>,
+    pub signature: Option<bool>,
+    /// Keys this parser does not know, comma lists joined back with `,`.
+    pub extra: IndexMap<String, String>,
+}
+
+// The value of an annotation key: one or more comma separated items.
+#[derive(Debug)]
+struct AnnotationValue<'a> {
+    first: &'a APIToken<'a>,
+    items: Vec<String>,
+}
+
+impl AnnotationValue<'_> {
+    fn single(&self, key: &str) -> Result<&str, PError> {
+        match self.items.as_slice() {
+            [item] => Ok(item),
+            _ => Err(PError::at(
+                self.first,
+                format!("`{key}` takes a single value"),
+            )),
+        }
+    }
+}
+
+impl ServerAnnotation {
+    fn from_pairs(pairs: Vec<(&APIToken, AnnotationValue)>) -> Result<Self, PError> {
+        let mut annotation = ServerAnnotation::default();
+        for (key, value) in pairs {
+            let key = key.text();
+            match key {
+                "group" => annotation.group = Some(value.single(key)?.to_string()),
+                "prefix" => annotation.prefix = Some(value.single(key)?.to_string()),
+                "jwt" => annotation.jwt = Some(value.single(key)?.to_string()),
+                "middleware" => annotation.middleware = value.items,
+                "timeout" => {
+                    let timeout = value.single(key)?;
+                    annotation.timeout = Some(parse_duration(timeout).ok_or_else(|| {
+                        PError::at(value.first, format!("invalid duration `{timeout}`"))
+                            .with_help("use a Go duration like 500ms, 3s or 1h30m")
+                    })?);
+                }
+                "maxBytes" => {
+                    let max_bytes = value.single(key)?;
+                    annotation.max_bytes = Some(max_bytes.parse().map_err(|_| {
+                        PError::at(value.first, format!("invalid byte count `{max_bytes}`"))
+                    })?);
+                }
+                "signature" => {
+                    annotation.signature = Some(match value.single(key)? {
+                        "true" => true,
+                        "false" => false,
+                        other => {
+                            return Err(PError::at(
+                                value.first,
+                                format!("invalid signature `{other}`, expected true or false"),
+                            ))
+                        }
+                    })
+                }
+                _ => {
+                    annotation
+                        .extra
+                        .insert(key.to_string(), value.items.join(","));
+                }
+            }
+        }
+        Ok(annotation)
+    }
+}
+
+/// Parses a Go duration string like `300ms`, `1.5h` or `2h45m`.
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    const UNITS: [(&str, f64); 7] = [
+        ("ns", 1e-9),
+        ("us", 1e-6),
+        ("µs", 1e-6),
+        ("ms", 1e-3),
+        ("s", 1.0),
+        ("m", 60.0),
+        ("h", 3600.0),
+    ];
+
+    // Go accepts a bare zero without a unit
+    if text == "0" {
+        return Some(Duration::ZERO);
+    }
+
+    let mut rest = text;
+    let mut seconds = 0.0;
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let number: f64 = rest[..number_len].parse().ok()?;
+        rest = &rest[number_len..];
+
+        let (unit, scale) = UNITS
+            .iter()
+            .filter(|(unit, _)| rest.starts_with(unit))
+            .max_by_key(|(unit, _)| unit.len())?;
+        seconds += number * scale;
+        rest = &rest[unit.len()..];
+    }
+
+    if text.is_empty() {
+        return None;
+    }
+    Duration::try_from_secs_f64(seconds).ok()
 }
 
+/// A `/`-separated part of a route path, `:id` is a parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RouteSegment {
+    Static(String),
+    Param(String),
+}
+
+/// A handler with the route it is served on, the block's `prefix` applied.
+/// Generators use it rather than `Handler::route` so that they agree on URLs.
 #[derive(Debug)]
+pub struct Route<'a> {
+    pub handler: &'a Handler,
+    pub segments: Vec<RouteSegment>,
+}
+
+impl Service {
+    /// The routes of the block's handlers, in declaration order.
+    pub fn routes(&self) -> impl Iterator<Item = Route<'_>> {
+        let prefix = self
+            .annotation
+            .prefix
+            .as_deref()
+            .map(parse_route)
+            .unwrap_or_default();
+
+        self.handlers.iter().map(move |handler| Route {
+            handler,
+            segments: prefix.iter().chain(&handler.route).cloned().collect(),
+        })
+    }
+}
+
+impl Route<'_> {
+    /// The route path in go-zero form, e.g. `/api/v1/users/:id`.
+    pub fn path(&self) -> String {
+        route_path(&self.segments, |name| format!(":{name}"))
+    }
+
+    /// The route path in OpenAPI form, e.g. `/api/v1/users/{id}`.
+    pub fn openapi_path(&self) -> String {
+        route_path(&self.segments, |name| format!("{{{name}}}"))
+    }
+
+    pub fn path_params(&self) -> impl Iterator<Item = &str> {
+        self.segments.iter().filter_map(|segment| match segment {
+            RouteSegment::Param(name) => Some(name.as_str()),
+            RouteSegment::Static(_) => None,
+        })
+    }
+}
+
+fn route_path(segments: &[RouteSegment], param: impl Fn(&str) -> String) -> String {
+    let path = segments
+        .iter()
+        .map(|segment| match segment {
+            RouteSegment::Static(name) => name.clone(),
+            RouteSegment::Param(name) => param(name),
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+    format!("/{path}")
+}
+
+fn parse_route(path: &str) -> Vec<RouteSegment> {
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| match segment.strip_prefix(':') {
+            Some(param) => RouteSegment::Param(param.to_string()),
+            None => RouteSegment::Static(segment.to_string()),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum HttpMethod {
     GET,
+    HEAD,
     POST,
+    PUT,
+    PATCH,
+    DELETE,
+    OPTIONS,
+    TRACE,
+}
+
+impl HttpMethod {
+    /// Whether go-zero parses form values from the body of requests with this
+    /// method, as `net/http` does, rather than from the query string only.
+    pub fn has_form_body(&self) -> bool {
+        matches!(self, HttpMethod::POST | HttpMethod::PUT | HttpMethod::PATCH)
+    }
 }
 
 impl std::fmt::Display for HttpMethod {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             HttpMethod::GET => write!(f, "get"),
+            HttpMethod::HEAD => write!(f, "head"),
             HttpMethod::POST => write!(f, "post"),
+            HttpMethod::PUT => write!(f, "put"),
+            HttpMethod::PATCH => write!(f, "patch"),
+            HttpMethod::DELETE => write!(f, "delete"),
+            HttpMethod::OPTIONS => write!(f, "options"),
+            HttpMethod::TRACE => write!(f, "trace"),
         }
     }
 }
@@ -43,90 +278,162 @@ impl std::fmt::Display for HttpMethod {
 pub fn parse_service(i: Input) -> IResult<Service> {
     tuple((
         opt(parse_service_anotation),
-        delimited(
+        preceded(
             match_token(Service),
-            match_token(Identifier),
-            match_token(OpenBrace),
+            cut(terminated(match_token(Identifier), match_token(OpenBrace))),
         ),
-        many0(parse_handler),
-        match_token(CloseBrace),
+        cut(many_till_token(parse_handler, CloseBrace)),
     ))(i)
-    .map(|(i, (anotation, name, handlers, _))| {
+    .map(|(i, (annotation, name, handlers))| {
         (
             i,
             Service {
                 name: name.at.to_string(),
-                anotation,
+                origin: Origin::of(name),
+                annotation: annotation.unwrap_or_default(),
                 handlers,
             },
         )
     })
 }
 
-fn parse_service_anotation(i: Input) -> IResult<IndexMap<String, String>> {
-    tuple((
+fn parse_service_anotation(i: Input) -> IResult<ServerAnnotation> {
+    let (i, pairs) = preceded(
         match_token(Server),
-        delimited(
+        cut(delimited(
             match_token(OpenParen),
             parse_kv_pairs,
             match_token(CloseParen),
-        ),
-    ))(i)
-    .map(|(i, (_, pairs))| (i, pairs.into_iter().collect::<IndexMap<_, _>>()))
+        )),
+    )(i)?;
+
+    match ServerAnnotation::from_pairs(pairs) {
+        Ok(annotation) => Ok((i, annotation)),
+        Err(e) => Err(nom::Err::Failure(e)),
+    }
 }
 
-fn parse_kv_pairs(i: Input) -> IResult<Vec<(String, String)>> {
+fn parse_kv_pairs<'a>(i: Input<'a>) -> IResult<'a, Vec<(&'a APIToken<'a>, AnnotationValue<'a>)>> {
     many0(tuple((
         match_token(Identifier),
-        match_token(Colon),
-        match_token(Identifier),
+        preceded(cut(match_token(Colon)), cut(parse_annotation_value)),
     )))(i)
-    .map(|(i, pairs)| {
-        (
-            i,
-            pairs
-                .into_iter()
-                .map(|(key, _, value)| (key.at.to_string(), value.at.to_string()))
-                .collect::<Vec<_>>(),
+}
+
+// parse_annotation_value parses comma separated items, each either a string
+// literal or adjacent words like `/api/v1`, `user/profile`, `3m` or `1048576`.
+fn parse_annotation_value<'a>(i: Input<'a>) -> IResult<'a, AnnotationValue<'a>> {
+    let (i, items) = separated_list1(
+        match_token(Comma),
+        alt((
+            map(match_token(StringLiteral), |t| vec![t]),
+            parse_annotation_words,
+        )),
+    )(i)?;
+
+    let value = AnnotationValue {
+        first: items[0][0],
+        items: items
+            .iter()
+            .map(|tokens| match tokens.as_slice() {
+                [t] if t.kind == StringLiteral => unquote(t.text()),
+                tokens => tokens.iter().map(|t| t.text()).collect(),
+            })
+            .collect(),
+    };
+    Ok((i, value))
+}
+
+fn parse_annotation_words<'a>(i: Input<'a>) -> IResult<'a, Vec<&'a APIToken<'a>>> {
+    let is_word = |kind: &APITokenKind| {
+        matches!(
+            kind,
+            Identifier
+                | Number
+                | RoutePath
+                | IntDataType
+                | FloatDataType
+                | StringDataType
+                | BoolDataType
+                | MapDataType
+                | Type
+                | Struct
+                | Service
+                | RespReturns
+                | Dot
         )
-    })
+    };
+
+    let words = i
+        .iter()
+        .enumerate()
+        .take_while(|(n, token)| {
+            is_word(&token.kind) && (*n == 0 || i[n - 1].span.end == token.span.start)
+        })
+        .count();
+    match words {
+        0 => Err(nom::Err::Error(PError::expected(
+            i,
+            Expected::Kind(Identifier),
+        ))),
+        n => Ok((&i[n..], i[..n].iter().collect())),
+    }
 }
 
 fn parse_handler(i: Input) -> IResult<Handler> {
-    tuple((
+    preceded(
         match_token(Handler),
-        match_token(Identifier),
-        parse_http_method,
-        match_token(RoutePath),
-        delimited(
-            opt(match_token(OpenParen)),
-            opt(match_token(Identifier)),
-            opt(match_token(CloseParen)),
-        ),
-        delimited(
-            tuple((opt(match_token(RespReturns)), opt(match_token(OpenParen)))),
-            opt(match_token(Identifier)),
-            opt(match_token(CloseParen)),
-        ),
-    ))(i)
-    .map(|(i, (_, name, method, path, req_type, resp_type))| {
+        cut(tuple((
+            match_token(Identifier),
+            parse_http_method,
+            match_token(RoutePath),
+            opt(parse_type_ref),
+            opt(preceded(match_token(RespReturns), cut(parse_type_ref))),
+        ))),
+    )(i)
+    .map(|(rest, (name, method, path, req_type, resp_type))| {
         (
-            i,
+            rest,
             Handler {
+                docs: docs_of(consumed(i, rest)),
                 name: name.at.to_string(),
+                origin: Origin::of(name),
                 method,
                 path: path.at.to_string(),
+                route: parse_route(path.at),
                 req_type: req_type.map(|t| t.at.to_string()),
                 resp_type: resp_type.map(|t| t.at.to_string()),
+                req_origin: req_type.map(Origin::of),
+                resp_origin: resp_type.map(Origin::of),
             },
         )
     })
 }
 
+// parse_type_ref parses the `(Name)` of a request or response type, an opening
+// parenthesis commits to the whole of it.
+fn parse_type_ref<'a>(i: Input<'a>) -> IResult<'a, &'a APIToken<'a>> {
+    delimited(
+        match_token(OpenParen),
+        cut(match_token(Identifier)),
+        cut(match_token(CloseParen)),
+    )(i)
+}
+
 fn parse_http_method(i: Input) -> IResult<HttpMethod> {
     alt((
         map(match_text_case_insensitive("GET"), |_| HttpMethod::GET),
+        map(match_text_case_insensitive("HEAD"), |_| HttpMethod::HEAD),
         map(match_text_case_insensitive("POST"), |_| HttpMethod::POST),
+        map(match_text_case_insensitive("PUT"), |_| HttpMethod::PUT),
+        map(match_text_case_insensitive("PATCH"), |_| HttpMethod::PATCH),
+        map(match_text_case_insensitive("DELETE"), |_| {
+            HttpMethod::DELETE
+        }),
+        map(match_text_case_insensitive("OPTIONS"), |_| {
+            HttpMethod::OPTIONS
+        }),
+        map(match_text_case_insensitive("TRACE"), |_| HttpMethod::TRACE),
     ))(i)
 }
 
@@ -189,6 +496,113 @@ mod tests {
         println!("{:#?}", kv_pairs_res);
     }
 
+    #[test]
+    fn it_parse_server_annotation_values() {
+        let source = r#"
+            @server (
+                group:      user/profile
+                prefix:     /api/v1
+                jwt:        Auth
+                middleware: Log, Cors
+                timeout:    1m30s
+                maxBytes:   1048576
+                signature:  true
+                summary:    "user profile"
+                tags:       a,b
+            )
+        "#;
+        let tokens = tokenize(source);
+        let annotation = parse_service_anotation(&tokens).unwrap().1;
+
+        assert_eq!(annotation.group.as_deref(), Some("user/profile"));
+        assert_eq!(annotation.prefix.as_deref(), Some("/api/v1"));
+        assert_eq!(annotation.jwt.as_deref(), Some("Auth"));
+        assert_eq!(annotation.middleware, vec!["Log", "Cors"]);
+        assert_eq!(annotation.timeout, Some(Duration::from_secs(90)));
+        assert_eq!(annotation.max_bytes, Some(1048576));
+        assert_eq!(annotation.signature, Some(true));
+        assert_eq!(
+            annotation.extra.get("summary").map(String::as_str),
+            Some("user profile")
+        );
+        assert_eq!(
+            annotation.extra.get("tags").map(String::as_str),
+            Some("a,b")
+        );
+
+        let tokens = tokenize("@server ( timeout: 1.5h )");
+        let annotation = parse_service_anotation(&tokens).unwrap().1;
+        assert_eq!(annotation.timeout, Some(Duration::from_secs(5400)));
+    }
+
+    #[test]
+    fn it_parse_server_annotation_errors() {
+        let cases = vec![
+            ("timeout: 3x", "invalid duration `3x`"),
+            (
+                "timeout: 99999999999999999999h",
+                "invalid duration `99999999999999999999h`",
+            ),
+            ("maxBytes: big", "invalid byte count `big`"),
+            (
+                "signature: yes",
+                "invalid signature `yes`, expected true or false",
+            ),
+            ("group: a, b", "`group` takes a single value"),
+        ];
+
+        for (pair, expected) in cases {
+            let source = format!("@server ( {pair} )");
+            let tokens = tokenize(&source);
+            let err = PError::from_nom(parse_service_anotation(&tokens).unwrap_err());
+            assert_eq!(err.summary(), expected, "{}", pair);
+        }
+    }
+
+    #[test]
+    fn it_service_routes_with_prefix() {
+        let source = r#"
+        @server (
+            prefix: /api/v1/
+        )
+        service user {
+            @handler getUser
+            get /users/:id (GetUserReq)
+
+            @handler listUsers
+            get / (ListUsersReq)
+        }
+        service user {
+            @handler ping
+            get /ping
+        }
+        "#;
+        let tokens = tokenize(source);
+        let (rest, service) = parse_service(&tokens).unwrap();
+
+        let paths = service.routes().map(|r| r.path()).collect::<Vec<_>>();
+        assert_eq!(paths, vec!["/api/v1/users/:id", "/api/v1"]);
+        let route = service.routes().next().unwrap();
+        assert_eq!(route.openapi_path(), "/api/v1/users/{id}");
+        assert_eq!(route.handler.name, "getUser");
+
+        let (_, service) = parse_service(rest).unwrap();
+        let paths = service.routes().map(|r| r.path()).collect::<Vec<_>>();
+        assert_eq!(paths, vec!["/ping"]);
+    }
+
+    #[test]
+    fn it_parse_duration() {
+        assert_eq!(parse_duration("3ms"), Some(Duration::from_millis(3)));
+        assert_eq!(parse_duration("3m"), Some(Duration::from_secs(180)));
+        assert_eq!(parse_duration("1.5h"), Some(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("2h45m"), Some(Duration::from_secs(9900)));
+        assert_eq!(parse_duration("0"), Some(Duration::ZERO));
+        assert_eq!(parse_duration("3"), None);
+        assert_eq!(parse_duration("99999999999999999999h"), None);
+        assert_eq!(parse_duration(""), None);
+    }
+
     #[test]
     fn it_parse_handler() {
         let source = r#"
@@ -201,4 +615,76 @@ mod tests {
         let handler_res = res.unwrap().1;
         println!("{:#?}", handler_res);
     }
+
+    #[test]
+    fn it_parse_handler_docs() {
+        let source = r#"
+            // getForm reads the form from the query
+            @handler getForm
+            get /form/req returns (GetFormReq) // deprecated
+        "#;
+        let tokens = tokenize(source);
+        let handler = parse_handler(&tokens).unwrap().1;
+
+        assert_eq!(
+            handler.docs,
+            vec!["getForm reads the form from the query", "deprecated"]
+        );
+    }
+
+    #[test]
+    fn it_parse_http_methods() {
+        let cases = vec![
+            ("get", HttpMethod::GET),
+            ("head", HttpMethod::HEAD),
+            ("post", HttpMethod::POST),
+            ("put", HttpMethod::PUT),
+            ("patch", HttpMethod::PATCH),
+            ("delete", HttpMethod::DELETE),
+            ("options", HttpMethod::OPTIONS),
+            ("trace", HttpMethod::TRACE),
+        ];
+
+        for (method, expected) in cases {
+            let source = format!("@handler user\n{method} /user returns (User)");
+            let tokens = tokenize(&source);
+            let handler = parse_handler(&tokens).unwrap().1;
+
+            assert_eq!(handler.method, expected, "{}", method);
+            assert_eq!(handler.method.to_string(), method);
+        }
+    }
+
+    #[test]
+    fn it_parse_route_params() {
+        let source = r#"
+            @server (
+                prefix: /api
+            )
+            service user {
+                @handler getUserFile
+                get /users/:id/user-files/v1.0/:name (GetUserFileReq)
+            }
+        "#;
+        let tokens = tokenize(source);
+        let service = parse_service(&tokens).unwrap().1;
+        let handler = &service.handlers[0];
+
+        assert_eq!(
+            handler.route,
+            vec![
+                RouteSegment::Static("users".to_string()),
+                RouteSegment::Param("id".to_string()),
+                RouteSegment::Static("user-files".to_string()),
+                RouteSegment::Static("v1.0".to_string()),
+                RouteSegment::Param("name".to_string()),
+            ]
+        );
+        let route = service.routes().next().unwrap();
+        assert_eq!(
+            route.openapi_path(),
+            "/api/users/{id}/user-files/v1.0/{name}"
+        );
+        assert_eq!(route.path_params().collect::<Vec<_>>(), vec!["id", "name"]);
+    }
 }
@@ -2,125 +2,379 @@
 
 use std::vec;
 
-use nom::combinator::{opt, success};
+use nom::combinator::{cut, opt};
 use nom::multi::many0;
-use nom::sequence::{delimited, tuple};
+use nom::sequence::{preceded, terminated, tuple};
 use nom::{branch::alt, combinator::map};
 use serde::ser::SerializeStruct;
-use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde::Serialize;
+use serde_json::{json, Value};
 
-use crate::common::{match_text, match_token, IResult, Input};
-use crate::token::APITokenKind::*;
+use crate::common::{
+    consumed, docs_of, many_till_token, match_text, match_token, IResult, Input, Origin,
+};
+use crate::error::{Expected, PError};
+use crate::tag::{parse_tag, Tag};
+use crate::token::{comment_text, APITokenKind::*};
 
 #[derive(Debug)]
 pub struct StructDef {
+    pub docs: Vec<String>,
     pub name: String,
+    pub origin: Origin,
     pub fields: Vec<Field>,
 }
 
+/// A named non-struct type, `type UserID int64`, or an alias, `type Tags = []string`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeAlias {
+    pub docs: Vec<String>,
+    pub name: String,
+    pub origin: Origin,
+    pub target: FieldType,
+}
+
+/// One declaration of a `type` statement.
 #[derive(Debug)]
+pub enum TypeDef {
+    Struct(StructDef),
+    Alias(TypeAlias),
+}
+
+impl TypeDef {
+    fn docs_mut(&mut self) -> &mut Vec<String> {
+        match self {
+            TypeDef::Struct(struct_def) => &mut struct_def.docs,
+            TypeDef::Alias(alias) => &mut alias.docs,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Field {
+    pub docs: Vec<String>,
     pub name: String,
+    pub origin: Origin,
+    /// Where the type is written, the name itself for an embedded struct.
+    pub type_origin: Origin,
     pub field_type: FieldType,
-    tag: Option<String>,
+    pub tag: Tag,
+}
+
+impl Field {
+    /// The name given to the field by the `key` tag, e.g. `id` for `path:"id"`.
+    pub fn tag_value(&self, key: &str) -> Option<&str> {
+        self.tag.get(key).map(|entry| entry.name.as_str())
+    }
+
+    /// Whether go-zero rejects a request without the field bound by `key`:
+    /// every field is required unless it is a pointer, or its tag marks it
+    /// `optional`, `omitempty` or gives it a `default=`. Embedded structs are
+    /// never required themselves.
+    pub fn is_required(&self, key: &str) -> bool {
+        if self.embedded().is_some() || matches!(self.field_type, FieldType::Pointer(_)) {
+            return false;
+        }
+        match self.tag.get(key) {
+            Some(entry) => {
+                !(entry.is_optional() || entry.is_omitempty() || entry.default().is_some())
+            }
+            None => true,
+        }
+    }
+
+    /// The struct an embedded field embeds, e.g. `Status` for a `Status` line.
+    pub fn embedded(&self) -> Option<&str> {
+        match &self.field_type {
+            FieldType::StructRef {
+                name,
+                is_embed: true,
+            } => Some(name),
+            _ => None,
+        }
+    }
+
+    /// The name the field is bound by under the `key` tag, falling back to the
+    /// Go name when the tag leaves it empty as in `json:",optional"`.
+    /// `None` when there is no such tag or it skips the field with `-`.
+    pub fn bound_name(&self, key: &str) -> Option<&str> {
+        match self.tag_value(key)? {
+            "-" => None,
+            "" => Some(&self.name),
+            name => Some(name),
+        }
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum FieldType {
     Int,
+    Int8,
+    Int16,
+    /// `int32`, or its alias `rune`
     Int32,
     Int64,
+    Uint,
+    /// `uint8`, or its alias `byte`
+    Uint8,
+    Uint16,
+    Uint32,
+    Uint64,
+    Float32,
+    Float64,
     String,
     Bool,
+    /// `any` or `interface{}`
+    Any,
+    /// `time.Time`
+    Time,
     Array(Box<FieldType>),
+    /// `[N]T`
+    FixedArray(usize, Box<FieldType>),
     Map(Box<FieldType>, Box<FieldType>),
-    StructRef { name: String, is_embed: bool },
+    /// `*T`, which may be `null`
+    Pointer(Box<FieldType>),
+    /// An anonymous `struct { ... }`
+    Inline(Vec<Field>),
+    StructRef {
+        name: String,
+        is_embed: bool,
+    },
 }
 
-impl Serialize for FieldType {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        match *self {
-            FieldType::Int => {
-                let mut state = serializer.serialize_struct("FieldType", 1)?;
-                state.serialize_field("type", "integer")?;
-                state.serialize_field("format", "int")?;
-                state.end()
-            }
-            FieldType::Int32 => {
-                let mut state = serializer.serialize_struct("FieldType", 1)?;
-                state.serialize_field("type", "integer")?;
-                state.serialize_field("format", "int32")?;
-                state.end()
+impl FieldType {
+    /// The predeclared Go type called `name`, e.g. `uint16` or `byte`.
+    pub fn scalar(name: &str) -> Option<FieldType> {
+        let scalar = match name {
+            "int" => FieldType::Int,
+            "int8" => FieldType::Int8,
+            "int16" => FieldType::Int16,
+            "int32" | "rune" => FieldType::Int32,
+            "int64" => FieldType::Int64,
+            "uint" => FieldType::Uint,
+            "uint8" | "byte" => FieldType::Uint8,
+            "uint16" => FieldType::Uint16,
+            "uint32" => FieldType::Uint32,
+            "uint64" => FieldType::Uint64,
+            "float32" => FieldType::Float32,
+            "float64" => FieldType::Float64,
+            "string" => FieldType::String,
+            "bool" => FieldType::Bool,
+            "any" => FieldType::Any,
+            _ => return None,
+        };
+        Some(scalar)
+    }
+
+    /// The fields of the anonymous struct this type is or holds, as in `[]struct{...}`.
+    pub fn inline_fields_mut(&mut self) -> Option<&mut Vec<Field>> {
+        match self {
+            FieldType::Inline(fields) => Some(fields),
+            FieldType::Array(inner)
+            | FieldType::FixedArray(_, inner)
+            | FieldType::Map(_, inner)
+            | FieldType::Pointer(inner) => inner.inline_fields_mut(),
+            _ => None,
+        }
+    }
+
+    /// Parses a tag literal such as a `default=` value as a value of this type.
+    /// `None` when it is not one, or the type has no literals.
+    pub fn literal(&self, text: &str) -> Option<Value> {
+        match self {
+            FieldType::Int | FieldType::Int64 => text.parse::<i64>().ok().map(Value::from),
+            FieldType::Int8 => text.parse::<i8>().ok().map(Value::from),
+            FieldType::Int16 => text.parse::<i16>().ok().map(Value::from),
+            FieldType::Int32 => text.parse::<i32>().ok().map(Value::from),
+            FieldType::Uint | FieldType::Uint64 => text.parse::<u64>().ok().map(Value::from),
+            FieldType::Uint8 => text.parse::<u8>().ok().map(Value::from),
+            FieldType::Uint16 => text.parse::<u16>().ok().map(Value::from),
+            FieldType::Uint32 => text.parse::<u32>().ok().map(Value::from),
+            FieldType::Float32 | FieldType::Float64 => text
+                .parse::<f64>()
+                .ok()
+                .filter(|f| f.is_finite())
+                .map(Value::from),
+            FieldType::String | FieldType::Any | FieldType::Time => Some(Value::from(text)),
+            FieldType::Bool => text.parse::<bool>().ok().map(Value::from),
+            FieldType::Pointer(inner) => inner.literal(text),
+            _ => None,
+        }
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        if let FieldType::Pointer(inner) = self {
+            return inner.is_numeric();
+        }
+        matches!(
+            self,
+            FieldType::Int
+                | FieldType::Int8
+                | FieldType::Int16
+                | FieldType::Int32
+                | FieldType::Int64
+                | FieldType::Uint
+                | FieldType::Uint8
+                | FieldType::Uint16
+                | FieldType::Uint32
+                | FieldType::Uint64
+                | FieldType::Float32
+                | FieldType::Float64
+        )
+    }
+
+    /// How the type reads in messages, e.g. `int64` or `[]string`.
+    pub fn describe(&self) -> String {
+        match self {
+            FieldType::Int => "int".to_string(),
+            FieldType::Int8 => "int8".to_string(),
+            FieldType::Int16 => "int16".to_string(),
+            FieldType::Int32 => "int32".to_string(),
+            FieldType::Int64 => "int64".to_string(),
+            FieldType::Uint => "uint".to_string(),
+            FieldType::Uint8 => "uint8".to_string(),
+            FieldType::Uint16 => "uint16".to_string(),
+            FieldType::Uint32 => "uint32".to_string(),
+            FieldType::Uint64 => "uint64".to_string(),
+            FieldType::Float32 => "float32".to_string(),
+            FieldType::Float64 => "float64".to_string(),
+            FieldType::String => "string".to_string(),
+            FieldType::Bool => "bool".to_string(),
+            FieldType::Any => "any".to_string(),
+            FieldType::Time => "time.Time".to_string(),
+            FieldType::Array(ele_type) => format!("[]{}", ele_type.describe()),
+            FieldType::FixedArray(len, ele_type) => format!("[{len}]{}", ele_type.describe()),
+            FieldType::Pointer(inner) => format!("*{}", inner.describe()),
+            FieldType::Inline(_) => "struct{...}".to_string(),
+            FieldType::Map(key_type, value_type) => {
+                format!("map[{}]{}", key_type.describe(), value_type.describe())
             }
-            FieldType::Int64 => {
-                let mut state = serializer.serialize_struct("FieldType", 1)?;
-                state.serialize_field("type", "integer")?;
-                state.serialize_field("format", "int64")?;
-                state.end()
+            FieldType::StructRef { name, .. } => name.clone(),
+        }
+    }
+
+    // schema lists the OpenAPI keywords describing the type, in output order.
+    // Integers narrower than their format are bounded with minimum/maximum.
+    fn schema(&self) -> Vec<(&'static str, Value)> {
+        let integer = |format: &str, bounds: Option<(i64, u64)>| {
+            let mut schema = vec![("type", json!("integer")), ("format", json!(format))];
+            if let Some((min, max)) = bounds {
+                schema.push(("minimum", json!(min)));
+                schema.push(("maximum", json!(max)));
             }
-            FieldType::String => {
-                let mut state = serializer.serialize_struct("FieldType", 1)?;
-                state.serialize_field("type", "string")?;
-                state.end()
+            schema
+        };
+
+        match self {
+            // Go's int and uint are 64 bits wide on the platforms go-zero targets
+            FieldType::Int | FieldType::Int64 => integer("int64", None),
+            FieldType::Int8 => integer("int32", Some((i8::MIN.into(), i8::MAX as u64))),
+            FieldType::Int16 => integer("int32", Some((i16::MIN.into(), i16::MAX as u64))),
+            FieldType::Int32 => integer("int32", None),
+            FieldType::Uint8 => integer("int32", Some((0, u8::MAX.into()))),
+            FieldType::Uint16 => integer("int32", Some((0, u16::MAX.into()))),
+            FieldType::Uint32 => integer("int64", Some((0, u32::MAX.into()))),
+            // no standard format holds a uint64
+            FieldType::Uint | FieldType::Uint64 => {
+                vec![("type", json!("integer")), ("minimum", json!(0))]
             }
-            FieldType::Bool => {
-                let mut state = serializer.serialize_struct("FieldType", 1)?;
-                state.serialize_field("type", "boolean")?;
-                state.end()
+            FieldType::Float32 => vec![("type", json!("number")), ("format", json!("float"))],
+            FieldType::Float64 => vec![("type", json!("number")), ("format", json!("double"))],
+            FieldType::String => vec![("type", json!("string"))],
+            FieldType::Bool => vec![("type", json!("boolean"))],
+            FieldType::Any => vec![],
+            FieldType::Time => vec![("type", json!("string")), ("format", json!("date-time"))],
+            // encoding/json writes byte slices as base64 strings
+            FieldType::Array(ele_type) if **ele_type == FieldType::Uint8 => {
+                vec![("type", json!("string")), ("format", json!("byte"))]
             }
-            FieldType::Array(ref ele_type) => {
-                let items = serde_json::to_value(ele_type).unwrap_or(json!({}));
-                let mut state = serializer.serialize_struct("FieldType", 2)?;
-                state.serialize_field("type", "array")?;
-                state.serialize_field("items", &items)?;
-                state.end()
+            FieldType::Array(ele_type) => vec![
+                ("type", json!("array")),
+                ("items", serde_json::to_value(ele_type).unwrap_or(json!({}))),
+            ],
+            // unlike slices, byte arrays are encoded as arrays of numbers
+            FieldType::FixedArray(len, ele_type) => vec![
+                ("type", json!("array")),
+                ("items", serde_json::to_value(ele_type).unwrap_or(json!({}))),
+                ("minItems", json!(len)),
+                ("maxItems", json!(len)),
+            ],
+            // OpenAPI 3.0 ignores the siblings of a `$ref`, so a nullable
+            // reference is wrapped in `allOf`
+            FieldType::Pointer(inner) => {
+                let mut schema = match **inner {
+                    FieldType::StructRef { .. } => {
+                        vec![(
+                            "allOf",
+                            json!([serde_json::to_value(inner).unwrap_or(json!({}))]),
+                        )]
+                    }
+                    _ => inner.schema(),
+                };
+                schema.push(("nullable", json!(true)));
+                schema
             }
-            FieldType::Map(ref _key_type, ref value_type) => {
-                let mut state = serializer.serialize_struct("FieldType", 2)?;
-                state.serialize_field("type", "object")?;
-                state.serialize_field(
+            FieldType::Map(_key_type, value_type) => vec![
+                ("type", json!("object")),
+                (
                     "additionalProperties",
-                    &serde_json::to_value(value_type).unwrap_or(json!({})),
-                )?;
-                state.end()
-            }
-            FieldType::StructRef { ref name, .. } => {
-                let mut state = serializer.serialize_struct("FieldType", 1)?;
-                state.serialize_field("$ref", &format!("#/components/schemas/{}", name))?;
-                state.end()
+                    serde_json::to_value(value_type).unwrap_or(json!({})),
+                ),
+            ],
+            // the generator describes the fields, they are named after their tags
+            FieldType::Inline(_) => vec![("type", json!("object"))],
+            FieldType::StructRef { name, .. } => {
+                vec![("$ref", json!(format!("#/components/schemas/{}", name)))]
             }
         }
     }
 }
 
+impl Serialize for FieldType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let schema = self.schema();
+        let mut state = serializer.serialize_struct("FieldType", schema.len())?;
+        for (key, value) in &schema {
+            state.serialize_field(key, value)?;
+        }
+        state.end()
+    }
+}
+
 // parse_struct_stmt parses a struct statement.
-pub fn parse_struct_stmt(i: Input) -> IResult<Vec<StructDef>> {
+pub fn parse_struct_stmt(i: Input) -> IResult<Vec<TypeDef>> {
     alt((parse_nest_struct, parse_many_struct))(i)
 }
 
-pub fn parse_struct_stmt1(input: Input) -> IResult<Vec<StructDef>> {
-    let mut structs = Vec::new();
+// parse_type_stmt parses one `type` statement, single or grouped.
+pub fn parse_type_stmt(i: Input) -> IResult<Vec<TypeDef>> {
+    alt((parse_nest_struct, parse_struct_to_vec))(i)
+}
+
+// parse_struct_stmt1 parses consecutive `type` statements.
+pub fn parse_struct_stmt1(input: Input) -> IResult<Vec<TypeDef>> {
+    let mut types = Vec::new();
     let mut i = input;
 
     while !i.is_empty() {
-        match alt((parse_nest_struct, parse_struct_to_vec))(i) {
-            Ok((next_input, mut parsed_structs)) => {
-                structs.append(&mut parsed_structs); // Append parsed structs to the collection
+        match parse_type_stmt(i) {
+            Ok((next_input, mut parsed_types)) => {
+                types.append(&mut parsed_types); // Append parsed types to the collection
                 i = next_input; // Update the input to the remaining unparsed part
             }
-            _ => break, // If parsing fails, exit the loop
+            Err(nom::Err::Error(_)) => break, // No struct starts here, leave it to the caller
+            Err(e) => return Err(e),          // A struct started but is malformed
         }
     }
 
-    Ok((i, structs))
+    Ok((i, types))
 }
 
 // parse_many_struct parses many struct statements.
-fn parse_many_struct(i: Input) -> IResult<Vec<StructDef>> {
+fn parse_many_struct(i: Input) -> IResult<Vec<TypeDef>> {
     many0(parse_one_struct)(i)
 }
 
@@ -133,87 +387,244 @@ fn parse_many_struct(i: Input) -> IResult<Vec<StructDef>> {
 ///     GetFormResp struct {
 ///         Total int64 `json:"total"`
 ///     }
+///     UserID int64
 /// )
-fn parse_nest_struct(i: Input) -> IResult<Vec<StructDef>> {
-    delimited(
+fn parse_nest_struct(i: Input) -> IResult<Vec<TypeDef>> {
+    preceded(
         tuple((match_token(Type), match_token(OpenParen))),
-        parse_many_struct,
-        match_token(CloseParen),
+        cut(many_till_token(
+            alt((parse_one_struct, parse_type_body)),
+            CloseParen,
+        )),
     )(i)
 }
 
-fn parse_struct_to_vec(i: Input) -> IResult<Vec<StructDef>> {
+fn parse_struct_to_vec(i: Input) -> IResult<Vec<TypeDef>> {
     map(parse_one_struct, |s| vec![s])(i)
 }
 
-// parse_one_struct parses a single struct statement.
-fn parse_one_struct(i: Input) -> IResult<StructDef> {
+// parse_one_struct parses a single `type` declaration, the keyword commits to it.
+// Only the members of a `type ( ... )` group may leave it out.
+fn parse_one_struct(i: Input) -> IResult<TypeDef> {
+    map(
+        tuple((match_token(Type), cut(parse_type_body))),
+        |(type_token, mut type_def)| {
+            type_def.docs_mut().splice(0..0, type_token.docs());
+            type_def
+        },
+    )(i)
+}
+
+fn parse_type_body(i: Input) -> IResult<TypeDef> {
+    alt((
+        map(parse_struct_body, TypeDef::Struct),
+        map(parse_alias_body, TypeDef::Alias),
+    ))(i)
+}
+
+// parse_alias_body parses a named non-struct type, `UserID int64` or `Tags = []string`.
+fn parse_alias_body(i: Input) -> IResult<TypeAlias> {
     tuple((
-        delimited(
-            opt(match_token(Type)),
-            match_token(Identifier),
-            opt(match_token(Struct)),
-        ),
-        match_token(OpenBrace),
-        many0(parse_field),
-        match_token(CloseBrace),
+        match_token(Identifier),
+        opt(match_token(Equals)),
+        parse_basic_field_type,
     ))(i)
-    .map(|(i, (name, _, fields, _))| {
+    .map(|(i, (name, _, target))| {
         (
             i,
-            StructDef {
+            TypeAlias {
+                docs: name.docs(),
                 name: name.at.to_string(),
-                fields,
+                origin: Origin::of(name),
+                target,
             },
         )
     })
 }
 
-fn parse_field(i: Input) -> IResult<Field> {
-    let (i, name_token) = match_token(Identifier)(i)?; // 解析字段名
+fn parse_struct_body(i: Input) -> IResult<StructDef> {
     tuple((
-        alt((
-            parse_basic_field_type,
-            success(FieldType::StructRef {
-                name: name_token.at.to_string(),
-                is_embed: true,
-            }),
-        )),
-        opt(match_token(TagAnnotation)),
+        terminated(match_token(Identifier), opt(match_token(Struct))),
+        match_token(OpenBrace),
+        cut(many_till_token(parse_field, CloseBrace)),
     ))(i)
-    .map(|(i, (data_type, tag_token))| {
+    .map(|(i, (name, _, fields))| {
         (
             i,
-            Field {
-                name: name_token.at.to_string(),
-                field_type: data_type,
-                tag: tag_token.map(|t| t.at.to_string()),
+            StructDef {
+                docs: name.docs(),
+                name: name.at.to_string(),
+                origin: Origin::of(name),
+                fields,
             },
         )
     })
 }
 
+fn parse_field(input: Input) -> IResult<Field> {
+    let (i, star) = opt(match_token(Star))(input)?; // 嵌入指针 *Base
+    let (i, name_token) = match_token(Identifier)(i)?; // 解析字段名
+
+    // A name alone on its line (optionally tagged) is an embedded struct.
+    let is_embed = match i.first() {
+        Some(next) => {
+            !name_token.is_same_line(next) || matches!(next.kind, TagAnnotation | CloseBrace)
+        }
+        None => true,
+    };
+    if let (Some(star), false) = (star, is_embed) {
+        return Err(nom::Err::Failure(
+            PError::at(star, "unexpected `*` before a field name")
+                .with_help("only embedded structs can be written as `*Name`"),
+        ));
+    }
+    let (i, field_type, type_origin) = if is_embed {
+        let embed_type = FieldType::StructRef {
+            name: name_token.at.to_string(),
+            is_embed: true,
+        };
+        (i, embed_type, Origin::of(name_token))
+    } else {
+        let (rest, field_type) = cut(parse_basic_field_type)(i)?;
+        let type_tokens = consumed(i, rest);
+        let type_origin = Origin {
+            file: 0,
+            span: type_tokens[0].span.start..type_tokens[type_tokens.len() - 1].span.end,
+        };
+        (rest, field_type, type_origin)
+    };
+    let (i, tag_token) = opt(match_token(TagAnnotation))(i)?;
+    let consumed = consumed(input, i);
+    // the comments inside an inline struct belong to its own fields
+    let docs = match field_type {
+        FieldType::Inline(_) => docs_of(&consumed[..1])
+            .into_iter()
+            .chain(
+                consumed
+                    .last()
+                    .and_then(|t| t.trailing_comment.map(comment_text)),
+            )
+            .collect(),
+        _ => docs_of(consumed),
+    };
+    let tag = match tag_token {
+        Some(token) => parse_tag(token).map_err(nom::Err::Failure)?,
+        None => Tag::default(),
+    };
+
+    Ok((
+        i,
+        Field {
+            docs,
+            name: name_token.at.to_string(),
+            origin: Origin::of(name_token),
+            type_origin,
+            field_type,
+            tag,
+        },
+    ))
+}
+
 // parse_field_type parses a basic field type.
+const FIELD_TYPE: Expected = Expected::Desc("a field type");
+
+// parse_basic_field_type parses any field type. When no alternative gets past the
+// first token it reports a single expectation rather than each alternative's.
 fn parse_basic_field_type(i: Input) -> IResult<FieldType> {
     alt((
-        map(match_text("string"), |_| FieldType::String),
-        map(match_text("int"), |_| FieldType::Int),
-        map(match_text("int32"), |_| FieldType::Int32),
-        map(match_text("int64"), |_| FieldType::Int64),
-        map(match_text("bool"), |_| FieldType::Bool),
+        parse_scalar_type,
+        parse_interface_type,
+        parse_time_type,
         parse_array_type,
+        parse_pointer_type,
+        parse_inline_struct,
         parse_map_type,
         parse_struct_ref,
     ))(i)
+    .map_err(|err| match err {
+        nom::Err::Error(e)
+            if e.span.as_ref().map(|s| s.start) == i.first().map(|t| t.span.start) =>
+        {
+            nom::Err::Error(PError::expected(i, FIELD_TYPE))
+        }
+        err => err,
+    })
+}
+
+// parse_scalar_type parses a predeclared type such as `int64` or `byte`, which
+// lex as data type keywords or plain identifiers.
+fn parse_scalar_type(i: Input) -> IResult<FieldType> {
+    let scalar = i.first().filter(|token| {
+        matches!(
+            token.kind,
+            IntDataType | FloatDataType | StringDataType | BoolDataType | Identifier
+        )
+    });
+    match scalar.and_then(|token| FieldType::scalar(token.text())) {
+        Some(field_type) => Ok((&i[1..], field_type)),
+        None => Err(nom::Err::Error(PError::expected(i, FIELD_TYPE))),
+    }
+}
+
+// parse_interface_type parses the empty interface, `interface{}`.
+fn parse_interface_type(i: Input) -> IResult<FieldType> {
+    map(
+        tuple((
+            match_text("interface"),
+            match_token(OpenBrace),
+            match_token(CloseBrace),
+        )),
+        |_| FieldType::Any,
+    )(i)
+}
+
+// parse_time_type parses `time.Time`, the only qualified type go-zero knows.
+fn parse_time_type(i: Input) -> IResult<FieldType> {
+    map(
+        tuple((
+            match_text("time"),
+            match_token(Dot),
+            cut(match_text("Time")),
+        )),
+        |_| FieldType::Time,
+    )(i)
 }
 
+// parse_array_type parses a slice `[]T` or an array `[N]T`, element types nest as in `[][]string`.
 fn parse_array_type(i: Input) -> IResult<FieldType> {
-    tuple((
+    let (i, (_, len, _, data_type)) = tuple((
         match_token(OpenBracket),
+        opt(match_token(Number)),
         match_token(CloseBracket),
         parse_basic_field_type,
-    ))(i)
-    .map(|(i, (_, _, data_type))| (i, FieldType::Array(Box::new(data_type))))
+    ))(i)?;
+
+    let field_type = match len {
+        Some(len) => {
+            let len = len
+                .text()
+                .parse::<usize>()
+                .map_err(|_| nom::Err::Failure(PError::at(len, "array length is too large")))?;
+            FieldType::FixedArray(len, Box::new(data_type))
+        }
+        None => FieldType::Array(Box::new(data_type)),
+    };
+    Ok((i, field_type))
+}
+
+// parse_pointer_type parses a pointer `*T`.
+fn parse_pointer_type(i: Input) -> IResult<FieldType> {
+    preceded(match_token(Star), cut(parse_basic_field_type))(i)
+        .map(|(i, data_type)| (i, FieldType::Pointer(Box::new(data_type))))
+}
+
+// parse_inline_struct parses an anonymous struct type, `struct { ... }`.
+fn parse_inline_struct(i: Input) -> IResult<FieldType> {
+    preceded(
+        tuple((match_token(Struct), match_token(OpenBrace))),
+        cut(many_till_token(parse_field, CloseBrace)),
+    )(i)
+    .map(|(i, fields)| (i, FieldType::Inline(fields)))
 }
 
 // parse_map_type parses a map type: map[keyType]valueType.
@@ -243,6 +654,16 @@ mod tests {
 
     use crate::token::tokenize;
 
+    // structs_of keeps the structs of parsed type declarations.
+    fn structs_of(defs: Vec<TypeDef>) -> Vec<StructDef> {
+        defs.into_iter()
+            .filter_map(|def| match def {
+                TypeDef::Struct(struct_def) => Some(struct_def),
+                TypeDef::Alias(_) => None,
+            })
+            .collect()
+    }
+
     #[test]
     fn test_parse_struct() {
         let source = r#"
@@ -287,10 +708,215 @@ mod tests {
         println!("{:#?}", field_var);
     }
 
+    #[test]
+    fn test_parse_field_tag() {
+        let source = r#"Name string `json:"name,optional" form:"name"`"#;
+        let input = tokenize(source);
+        let (_, field) = parse_field(&input).unwrap();
+
+        assert_eq!(field.tag_value("json"), Some("name"));
+        assert!(field.tag.get("json").unwrap().is_optional());
+        assert_eq!(field.tag_value("path"), None);
+
+        let source = r#"
+        type User struct {
+            Name string `json:name`
+        }
+        "#;
+        let input = tokenize(source);
+        let err = match parse_struct_stmt1(&input) {
+            Err(nom::Err::Failure(err)) => err,
+            other => panic!("expected a failure, got {other:?}"),
+        };
+        assert_eq!(&source[err.span.unwrap()], "json");
+    }
+
+    #[test]
+    fn test_parse_struct_docs() {
+        let source = r#"
+        // Status is the result of every call
+        type Status struct {
+            // Code is 0 on success
+            Code int `json:"code"`
+            Msg  string `json:"msg"` // error message
+        }
+        "#;
+        let input = tokenize(source);
+        let structs = structs_of(parse_struct_stmt1(&input).unwrap().1);
+
+        assert_eq!(structs[0].docs, vec!["Status is the result of every call"]);
+        assert_eq!(structs[0].fields[0].docs, vec!["Code is 0 on success"]);
+        assert_eq!(structs[0].fields[1].docs, vec!["error message"]);
+    }
+
+    #[test]
+    fn test_parse_embed_before_field() {
+        let source = r#"
+        type GetFormResp struct {
+            Status
+            Total int64 `json:"total"`
+        }
+        "#;
+        let input = tokenize(source);
+        let (rest, defs) = parse_struct_stmt1(&input).unwrap();
+        let structs = structs_of(defs);
+
+        assert!(rest.is_empty());
+        let fields = &structs[0].fields;
+        assert_eq!(
+            fields[0].field_type,
+            FieldType::StructRef {
+                name: "Status".to_string(),
+                is_embed: true,
+            }
+        );
+        assert_eq!(fields[1].name, "Total");
+        assert_eq!(fields[1].field_type, FieldType::Int64);
+    }
+
+    #[test]
+    fn test_parse_pointer_embed() {
+        let source = r#"
+        type Resp struct {
+            *Status
+            Total int64 `json:"total"`
+        }
+        "#;
+        let input = tokenize(source);
+        let structs = structs_of(parse_struct_stmt1(&input).unwrap().1);
+        assert_eq!(structs[0].fields[0].embedded(), Some("Status"));
+
+        let source = r#"
+        type Resp struct {
+            *Total int64
+        }
+        "#;
+        let input = tokenize(source);
+        let err = match parse_struct_stmt1(&input) {
+            Err(nom::Err::Failure(err)) => err,
+            other => panic!("expected a failure, got {other:?}"),
+        };
+        assert_eq!(err.summary(), "unexpected `*` before a field name");
+    }
+
+    #[test]
+    fn test_parse_inline_struct() {
+        let source = r#"
+        type User struct {
+            // where the user lives
+            Address struct {
+                City string `json:"city"` // the city name
+                Geo  struct {
+                    Lat float64 `json:"lat"`
+                } `json:"geo,optional"`
+            } `json:"address"`
+            Tags []struct {
+                Name string `json:"name"`
+            } `json:"tags"`
+        }
+        "#;
+        let input = tokenize(source);
+        let (rest, defs) = parse_struct_stmt1(&input).unwrap();
+        let structs = structs_of(defs);
+        assert!(rest.is_empty());
+
+        let address = &structs[0].fields[0];
+        assert_eq!(address.docs, vec!["where the user lives"]);
+        assert_eq!(address.tag_value("json"), Some("address"));
+        let FieldType::Inline(fields) = &address.field_type else {
+            panic!("expected an inline struct, got {:?}", address.field_type);
+        };
+        assert_eq!(fields[0].docs, vec!["the city name"]);
+        assert!(matches!(fields[1].field_type, FieldType::Inline(ref geo) if geo.len() == 1));
+        assert!(matches!(
+            structs[0].fields[1].field_type,
+            FieldType::Array(ref ele) if matches!(**ele, FieldType::Inline(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_type_alias() {
+        let source = r#"
+        // UserID identifies a user
+        type UserID int64
+        type Tags = []string
+        type (
+            Scores map[string]float64
+            User {
+                ID UserID `json:"id"`
+            }
+        )
+        "#;
+        let input = tokenize(source);
+        let (rest, defs) = parse_struct_stmt1(&input).unwrap();
+        assert!(rest.is_empty());
+
+        let aliases = defs
+            .iter()
+            .filter_map(|def| match def {
+                TypeDef::Alias(alias) => Some(alias),
+                TypeDef::Struct(_) => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(aliases.len(), 3);
+        assert_eq!(aliases[0].name, "UserID");
+        assert_eq!(aliases[0].docs, vec!["UserID identifies a user"]);
+        assert_eq!(aliases[0].target, FieldType::Int64);
+        assert_eq!(
+            aliases[1].target,
+            FieldType::Array(Box::new(FieldType::String))
+        );
+        assert_eq!(
+            aliases[2].target,
+            FieldType::Map(Box::new(FieldType::String), Box::new(FieldType::Float64))
+        );
+        assert!(matches!(&defs[3], TypeDef::Struct(user) if user.name == "User"));
+    }
+
     #[test]
     fn test_parse_basic_field_type() {
         let source = vec![
             ("test1", "int", FieldType::Int),
+            ("test1_1", "uint16", FieldType::Uint16),
+            ("test1_2", "byte", FieldType::Uint8),
+            ("test1_3", "rune", FieldType::Int32),
+            ("test1_4", "float64", FieldType::Float64),
+            ("test1_5", "any", FieldType::Any),
+            ("test1_6", "interface{}", FieldType::Any),
+            ("test1_7", "time.Time", FieldType::Time),
+            (
+                "test1_8",
+                "[]byte",
+                FieldType::Array(Box::new(FieldType::Uint8)),
+            ),
+            (
+                "test1_9",
+                "[][]string",
+                FieldType::Array(Box::new(FieldType::Array(Box::new(FieldType::String)))),
+            ),
+            (
+                "test1_10",
+                "[4]int",
+                FieldType::FixedArray(4, Box::new(FieldType::Int)),
+            ),
+            (
+                "test1_11",
+                "[]*User",
+                FieldType::Array(Box::new(FieldType::Pointer(Box::new(
+                    FieldType::StructRef {
+                        name: "User".to_string(),
+                        is_embed: false,
+                    },
+                )))),
+            ),
+            (
+                "test1_12",
+                "map[string]*int64",
+                FieldType::Map(
+                    Box::new(FieldType::String),
+                    Box::new(FieldType::Pointer(Box::new(FieldType::Int64))),
+                ),
+            ),
             ("test2", "int32", FieldType::Int32),
             ("test3", "int64", FieldType::Int64),
             ("test4", "string", FieldType::String),
@@ -334,6 +960,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_field_type_error() {
+        for (source, expected) in [
+            (
+                "type User struct {\n Name = string\n}",
+                "expected a field type, found Equals `=`",
+            ),
+            (
+                "type User struct {\n Tags []= \n}",
+                "expected a field type, found Equals `=`",
+            ),
+            (
+                "type User struct {\n Tags map string\n}",
+                "expected OpenBracket, found StringDataType `string`",
+            ),
+        ] {
+            let input = tokenize(source);
+            let err = match parse_struct_stmt1(&input) {
+                Err(nom::Err::Failure(err)) => err,
+                other => panic!("expected a failure, got {other:?}"),
+            };
+            assert_eq!(err.summary(), expected, "{source}");
+        }
+    }
+
     #[test]
     fn test_parse_struct_ref() {
         let source = r#"
@@ -352,7 +1003,7 @@ mod tests {
             (
                 "test1",
                 FieldType::Int,
-                r#"{"type":"integer","format":"int"}"#,
+                r#"{"type":"integer","format":"int64"}"#,
             ),
             (
                 "test2",
@@ -369,12 +1020,66 @@ mod tests {
             (
                 "test6",
                 FieldType::Array(Box::new(FieldType::Int)),
-                r#"{"type":"array","items":{"format":"int","type":"integer"}}"#,
+                r#"{"type":"array","items":{"format":"int64","type":"integer"}}"#,
             ),
             (
                 "test7",
                 FieldType::Map(Box::new(FieldType::String), Box::new(FieldType::Int)),
-                r#"{"type":"object","additionalProperties":{"format":"int","type":"integer"}}"#,
+                r#"{"type":"object","additionalProperties":{"format":"int64","type":"integer"}}"#,
+            ),
+            (
+                "test8",
+                FieldType::Uint8,
+                r#"{"type":"integer","format":"int32","minimum":0,"maximum":255}"#,
+            ),
+            (
+                "test9",
+                FieldType::Int16,
+                r#"{"type":"integer","format":"int32","minimum":-32768,"maximum":32767}"#,
+            ),
+            (
+                "test10",
+                FieldType::Uint64,
+                r#"{"type":"integer","minimum":0}"#,
+            ),
+            (
+                "test11",
+                FieldType::Float32,
+                r#"{"type":"number","format":"float"}"#,
+            ),
+            (
+                "test12",
+                FieldType::Float64,
+                r#"{"type":"number","format":"double"}"#,
+            ),
+            ("test13", FieldType::Any, r#"{}"#),
+            (
+                "test14",
+                FieldType::Time,
+                r#"{"type":"string","format":"date-time"}"#,
+            ),
+            (
+                "test15",
+                FieldType::Array(Box::new(FieldType::Uint8)),
+                r#"{"type":"string","format":"byte"}"#,
+            ),
+            (
+                "test16",
+                FieldType::FixedArray(2, Box::new(FieldType::Uint8)),
+                r#"{"type":"array","items":{"format":"int32","maximum":255,"minimum":0,"type":"integer"},"minItems":2,"maxItems":2}"#,
+            ),
+            (
+                "test17",
+                FieldType::Pointer(Box::new(FieldType::String)),
+                r#"{"type":"string","nullable":true}"#,
+            ),
+            (
+                "test18",
+                FieldType::Pointer(Box::new(FieldType::StructRef {
+                    name: "User".to_string(),
+                    is_embed: false,
+                })),
+                r##"{"allOf":[{"$ref":"#/components/schemas/User"}],"nullable":true}"##,
             ),
         ];
 
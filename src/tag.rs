@@ -0,0 +1,329 @@
+#![allow(dead_code)]
+
+use std::ops::Range;
+
+use crate::error::PError;
+use crate::token::APIToken;
+
+/// A parsed struct field tag.
+///
+/// ```text
+/// `json:"name,optional" form:"name,default=anonymous" validate:"required"`
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Tag {
+    pub entries: Vec<TagEntry>,
+}
+
+impl Tag {
+    /// The entry for `key`, e.g. the `json` entry of `json:"name"`.
+    pub fn get(&self, key: &str) -> Option<&TagEntry> {
+        self.entries.iter().find(|entry| entry.key == key)
+    }
+}
+
+/// One `key:"name,option,..."` pair of a tag.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagEntry {
+    pub key: String,
+    /// The name before the first comma, may be empty (`json:",optional"`) or `-`.
+    pub name: String,
+    pub options: Vec<TagOption>,
+    /// Byte range of the whole `key:"..."` pair in the source.
+    pub span: Range<usize>,
+}
+
+impl TagEntry {
+    pub fn is_optional(&self) -> bool {
+        self.options.contains(&TagOption::Optional)
+    }
+
+    pub fn is_omitempty(&self) -> bool {
+        self.options.contains(&TagOption::OmitEmpty)
+    }
+
+    pub fn default(&self) -> Option<&str> {
+        self.options.iter().find_map(|option| match option {
+            TagOption::Default(value) => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn enum_options(&self) -> Option<&[String]> {
+        self.options.iter().find_map(|option| match option {
+            TagOption::Options(values) => Some(values.as_slice()),
+            _ => None,
+        })
+    }
+
+    pub fn range(&self) -> Option<&TagRange> {
+        self.options.iter().find_map(|option| match option {
+            TagOption::Range(range) => Some(range),
+            _ => None,
+        })
+    }
+}
+
+/// The options go-zero understands after a tag name, anything else is kept verbatim.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TagOption {
+    /// `optional`
+    Optional,
+    /// `omitempty`
+    OmitEmpty,
+    /// `default=value`
+    Default(String),
+    /// `options=a|b|c` or `options=[a,b,c]`
+    Options(Vec<String>),
+    /// `range=[1:10]`, `range=(0:1]`, `range=[1:]`
+    Range(TagRange),
+    Other(String),
+}
+
+/// A numeric interval, either bound may be left open as in `[1:]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagRange {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub min_inclusive: bool,
+    pub max_inclusive: bool,
+}
+
+/// Parses the backtick quoted text of a `TagAnnotation` token, errors point into the tag.
+pub fn parse_tag(token: &APIToken) -> Result<Tag, PError> {
+    let text = token.text();
+    // offset of the tag body in the source, after the opening backtick
+    let base = token.span.start + 1;
+    let body = text
+        .strip_prefix('`')
+        .and_then(|t| t.strip_suffix('`'))
+        .unwrap_or(text);
+
+    let mut entries: Vec<TagEntry> = vec![];
+    let mut pos = 0;
+    loop {
+        pos += body[pos..].len() - body[pos..].trim_start().len();
+        if pos == body.len() {
+            break;
+        }
+
+        let start = pos;
+        let key_len = body[pos..]
+            .find(|c: char| c == ':' || c == '"' || c.is_whitespace())
+            .unwrap_or(body.len() - pos);
+        let key = &body[pos..pos + key_len];
+        pos += key_len;
+        if key.is_empty() || !body[pos..].starts_with(":\"") {
+            return Err(tag_error(
+                base + start..base + pos.max(start + 1).min(body.len()),
+                "malformed tag, expected `key:\"value\"`",
+            )
+            .with_help("tags are space separated pairs, e.g. `json:\"name\" form:\"name\"`"));
+        }
+        pos += 2;
+
+        let value_start = pos;
+        let mut escaped = false;
+        let value_len = body[pos..].find(|c: char| {
+            let end = !escaped && c == '"';
+            escaped = !escaped && c == '\\';
+            end
+        });
+        let Some(value_len) = value_len else {
+            return Err(tag_error(
+                base + start..base + body.len(),
+                format!("unterminated value for tag key `{key}`"),
+            ));
+        };
+        pos += value_len + 1;
+
+        let span = base + start..base + pos;
+        if entries.iter().any(|entry| entry.key == key) {
+            return Err(tag_error(span, format!("duplicate tag key `{key}`")));
+        }
+        let value = &body[value_start..value_start + value_len];
+        let (name, options) = parse_value(value, base + value_start)?;
+        entries.push(TagEntry {
+            key: key.to_string(),
+            name,
+            options,
+            span,
+        });
+    }
+
+    Ok(Tag { entries })
+}
+
+// parse_value splits `name,option,...`, commas inside `[...]` do not separate options.
+fn parse_value(value: &str, offset: usize) -> Result<(String, Vec<TagOption>), PError> {
+    let mut parts = vec![];
+    let (mut depth, mut start) = (0, 0);
+    for (idx, ch) in value.char_indices() {
+        match ch {
+            '[' | '(' => depth += 1,
+            ']' | ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push((start, &value[start..idx]));
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push((start, &value[start..]));
+
+    let mut parts = parts.into_iter();
+    let name = parts.next().map(|(_, name)| name).unwrap_or_default();
+    let options = parts
+        .map(|(start, option)| parse_option(option, offset + start..offset + start + option.len()))
+        .collect::<Result<_, _>>()?;
+    Ok((name.to_string(), options))
+}
+
+fn parse_option(option: &str, span: Range<usize>) -> Result<TagOption, PError> {
+    let parsed = match option.split_once('=') {
+        None if option == "optional" => TagOption::Optional,
+        None if option == "omitempty" => TagOption::OmitEmpty,
+        Some(("default", value)) => TagOption::Default(value.to_string()),
+        Some(("options", value)) => {
+            let values = match value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+                Some(list) => list.split(',').map(str::trim).collect::<Vec<_>>(),
+                None => value.split('|').collect(),
+            };
+            if values.iter().any(|v| v.is_empty()) {
+                return Err(tag_error(span, format!("invalid options `{value}`"))
+                    .with_help("expected options=a|b|c or options=[a,b,c]"));
+            }
+            TagOption::Options(values.into_iter().map(String::from).collect())
+        }
+        Some(("range", value)) => match parse_range(value) {
+            Some(range) => TagOption::Range(range),
+            None => {
+                return Err(tag_error(span, format!("invalid range `{value}`"))
+                    .with_help("expected an interval such as range=[1:10] or range=(0:1]"))
+            }
+        },
+        _ => TagOption::Other(option.to_string()),
+    };
+    Ok(parsed)
+}
+
+// parse_range parses go-zero's interval notation, `[` and `]` include a bound, `(` and `)` exclude it.
+fn parse_range(value: &str) -> Option<TagRange> {
+    let min_inclusive = match value.chars().next()? {
+        '[' => true,
+        '(' => false,
+        _ => return None,
+    };
+    let max_inclusive = match value.chars().last()? {
+        ']' => true,
+        ')' => false,
+        _ => return None,
+    };
+    let (min, max) = value.get(1..value.len() - 1)?.split_once(':')?;
+    let bound = |b: &str| match b.trim() {
+        "" => Some(None),
+        b => b.parse::<f64>().ok().map(Some),
+    };
+    let (min, max) = (bound(min)?, bound(max)?);
+    if let (Some(min), Some(max)) = (min, max) {
+        if min > max {
+            return None;
+        }
+    }
+
+    Some(TagRange {
+        min,
+        max,
+        min_inclusive,
+        max_inclusive,
+    })
+}
+
+fn tag_error(span: Range<usize>, message: impl Into<String>) -> PError {
+    PError {
+        span: Some(span),
+        ..PError::new(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::{APITokenKind, APITokenizer};
+
+    fn tag_of(source: &str) -> Result<Tag, PError> {
+        let tokens = APITokenizer::new(source).collect::<Vec<_>>();
+        let token = tokens
+            .iter()
+            .find(|t| t.kind == APITokenKind::TagAnnotation)
+            .expect("a tag token");
+        parse_tag(token)
+    }
+
+    #[test]
+    fn it_parse_tag() {
+        let tag = tag_of(
+            r#"Gender string `json:"gender,optional,options=male|female" form:"gender,default=male" validate:"required"`"#,
+        )
+        .unwrap();
+
+        let json = tag.get("json").unwrap();
+        assert_eq!(json.name, "gender");
+        assert!(json.is_optional());
+        assert_eq!(
+            json.enum_options(),
+            Some(&["male".to_string(), "female".to_string()][..])
+        );
+        assert_eq!(tag.get("form").unwrap().default(), Some("male"));
+        assert_eq!(tag.get("validate").unwrap().name, "required");
+        assert!(tag.get("path").is_none());
+    }
+
+    #[test]
+    fn it_parse_tag_options_and_range() {
+        let tag =
+            tag_of(r#"Age int `json:"age,omitempty,range=(0:150]" form:"kind,options=[a, b]"`"#)
+                .unwrap();
+
+        let json = tag.get("json").unwrap();
+        assert!(json.is_omitempty());
+        assert_eq!(
+            json.range(),
+            Some(&TagRange {
+                min: Some(0.0),
+                max: Some(150.0),
+                min_inclusive: false,
+                max_inclusive: true,
+            })
+        );
+        assert_eq!(
+            tag.get("form").unwrap().enum_options(),
+            Some(&["a".to_string(), "b".to_string()][..])
+        );
+
+        let open = tag_of(r#"Page int `form:"page,range=[1:]"`"#).unwrap();
+        assert_eq!(open.get("form").unwrap().range().unwrap().max, None);
+    }
+
+    #[test]
+    fn it_rejects_malformed_tags() {
+        let source = r#"Name string `json:name`"#;
+        let err = tag_of(source).unwrap_err();
+        assert_eq!(err.summary(), "malformed tag, expected `key:\"value\"`");
+        assert_eq!(&source[err.span.unwrap()], "json");
+
+        let source = r#"Age int `json:"age,range=[10:1]"`"#;
+        let err = tag_of(source).unwrap_err();
+        assert_eq!(err.summary(), "invalid range `[10:1]`");
+        assert_eq!(&source[err.span.unwrap()], "range=[10:1]");
+
+        let err = tag_of(r#"Age int `json:"age" json:"years"`"#).unwrap_err();
+        assert_eq!(err.summary(), "duplicate tag key `json`");
+
+        let err = tag_of(r#"Age int `json:"age`"#).unwrap_err();
+        assert_eq!(err.summary(), "unterminated value for tag key `json`");
+
+        let err = tag_of(r#"Kind string `json:"kind,options=a||b"`"#).unwrap_err();
+        assert_eq!(err.summary(), "invalid options `a||b`");
+    }
+}
@@ -18,18 +18,48 @@ impl<'a> APITokenizer<'a> {
     }
 }
 
+impl<'a> APITokenizer<'a> {
+    // A comment that follows a token on the same line belongs to that token.
+    fn trailing_comment(&mut self, end: usize) -> Option<&'a str> {
+        let mut peek = self.lexer.clone();
+        match peek.next() {
+            Some(Ok(APITokenKind::LineComment | APITokenKind::BlockComment))
+                if !self.source[end..peek.span().start].contains('\n') =>
+            {
+                let comment = peek.slice();
+                self.lexer = peek;
+                Some(comment)
+            }
+            _ => None,
+        }
+    }
+}
+
 impl<'a> Iterator for APITokenizer<'a> {
     type Item = APIToken<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.lexer.next() {
-            Some(Ok(kind)) => Some(APIToken {
+        let mut comments = vec![];
+        loop {
+            let kind = match self.lexer.next()? {
+                // Comments are not tokens of their own, they are kept on the next token.
+                Ok(APITokenKind::LineComment | APITokenKind::BlockComment) => {
+                    comments.push(self.lexer.slice());
+                    continue;
+                }
+                Ok(kind) => kind,
+                // Keep going after an unrecognised character, the parser reports it.
+                Err(()) => APITokenKind::Error,
+            };
+            let span = self.lexer.span();
+            return Some(APIToken {
                 source: self.source,
                 kind,
                 at: self.lexer.slice(),
-                span: self.lexer.span(),
-            }),
-            _ => None,
+                trailing_comment: self.trailing_comment(span.end),
+                span,
+                comments,
+            });
         }
     }
 }
@@ -40,12 +70,45 @@ pub struct APIToken<'a> {
     pub kind: APITokenKind,
     pub at: &'a str,
     pub span: Range<usize>,
+    /// Comments between the previous token and this one.
+    pub comments: Vec<&'a str>,
+    /// A comment following this token on the same line.
+    pub trailing_comment: Option<&'a str>,
 }
 
 impl<'a> APIToken<'a> {
     pub fn text(&self) -> &'a str {
         &self.source[self.span.clone()]
     }
+
+    /// Whether `next` starts on the line this token ends on.
+    pub fn is_same_line(&self, next: &APIToken) -> bool {
+        !self.source[self.span.end..next.span.start].contains('\n')
+    }
+
+    /// The text of the comments leading this token, without comment markers.
+    pub fn docs(&self) -> Vec<String> {
+        self.comments.iter().map(|c| comment_text(c)).collect()
+    }
+}
+
+/// Strips the `//` or `/* */` markers (and the leading `*` of block comment lines).
+pub fn comment_text(comment: &str) -> String {
+    if let Some(line) = comment.strip_prefix("//") {
+        return line.trim().to_string();
+    }
+
+    let block = comment
+        .strip_prefix("/*")
+        .and_then(|c| c.strip_suffix("*/"))
+        .unwrap_or(comment);
+    block
+        .lines()
+        .map(|line| line.trim().trim_start_matches('*').trim())
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
 }
 
 impl std::fmt::Debug for APIToken<'_> {
@@ -61,14 +124,29 @@ impl std::fmt::Debug for APIToken<'_> {
     }
 }
 
-pub fn tokenize(source: &str) -> Vec<APIToken> {
+pub fn tokenize(source: &str) -> Vec<APIToken<'_>> {
     APITokenizer::new(source).collect::<Vec<_>>()
 }
 
+// Bumps the lexer past the closing `*/`, an unterminated comment is an error.
+fn block_comment(lex: &mut Lexer<APITokenKind>) -> bool {
+    match lex.remainder().find("*/") {
+        Some(end) => {
+            lex.bump(end + 2);
+            true
+        }
+        None => false,
+    }
+}
+
 #[derive(Logos, Debug, PartialEq, Clone)]
 pub enum APITokenKind {
     #[regex(r"[ \t\r\n\f]+", logos::skip)]
     Whitespace,
+    #[regex(r"//[^\n]*")]
+    LineComment,
+    #[token("/*", block_comment)]
+    BlockComment,
 
     // basic tokens
     #[token("{")]
@@ -85,6 +163,16 @@ pub enum APITokenKind {
     CloseBracket,
     #[token(":")]
     Colon,
+    #[token(",")]
+    Comma,
+    #[token("=")]
+    Equals,
+    #[token(".")]
+    Dot,
+    #[token("*")]
+    Star,
+    #[regex(r#""([^"\\\n]|\\.)*""#)]
+    StringLiteral,
 
     // data types
     #[regex("int(8|16|32|64)?")]
@@ -101,13 +189,16 @@ pub enum APITokenKind {
     // type modifiers
     #[token("type")]
     Type,
+    #[regex("[0-9]+", priority = 10)]
+    Number,
     // #[regex(r#"[_a-zA-Z][_$a-zA-Z0-9]*"#)]
     #[regex(r"[_a-zA-Z][_$a-zA-Z0-9]*|[0-9]+[_$a-zA-Z0-9]*")]
     Identifier,
     #[token("struct")]
     Struct,
     // #[regex(r#"`[a-zA-Z0-9_]+:"[^"]+(?:,[^"]+)*"`"#)]
-    #[regex(r#"`(?:[a-zA-Z0-9_]+:"[^"]*"(?:\s+)?)+`"#)]
+    // the tag body is checked by tag::parse_tag, which reports malformed tags precisely
+    #[regex(r"`[^`]*`")]
     TagAnnotation,
 
     // service modifiers
@@ -117,18 +208,21 @@ pub enum APITokenKind {
     Service,
     #[token("@handler")]
     Handler,
-    #[regex("get|post")]
-    HttpMethod,
-    #[regex(r#"/([a-zA-Z0-9_]+/?)*"#)]
+    #[regex(r#"/(:?[a-zA-Z0-9_.\-]+/?)*"#)]
     RoutePath,
     #[token("returns")]
     RespReturns,
+
+    // text that no other token matches, produced by the tokenizer rather than logos
+    Error,
 }
 
 impl std::fmt::Display for APITokenKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             APITokenKind::Whitespace => write!(f, "Whitespace"),
+            APITokenKind::LineComment => write!(f, "LineComment"),
+            APITokenKind::BlockComment => write!(f, "BlockComment"),
             APITokenKind::OpenBrace => write!(f, "OpenBrace"),
             APITokenKind::CloseBrace => write!(f, "CloseBrace"),
             APITokenKind::OpenParen => write!(f, "OpenParen"),
@@ -136,21 +230,27 @@ impl std::fmt::Display for APITokenKind {
             APITokenKind::OpenBracket => write!(f, "OpenBracket"),
             APITokenKind::CloseBracket => write!(f, "CloseBracket"),
             APITokenKind::Colon => write!(f, "Colon"),
+            APITokenKind::Comma => write!(f, "Comma"),
+            APITokenKind::Equals => write!(f, "Equals"),
+            APITokenKind::Dot => write!(f, "Dot"),
+            APITokenKind::Star => write!(f, "Star"),
+            APITokenKind::StringLiteral => write!(f, "StringLiteral"),
             APITokenKind::IntDataType => write!(f, "IntDataType"),
             APITokenKind::FloatDataType => write!(f, "FloatDataType"),
             APITokenKind::StringDataType => write!(f, "StringDataType"),
             APITokenKind::BoolDataType => write!(f, "BoolDataType"),
             APITokenKind::MapDataType => write!(f, "MapDataType"),
             APITokenKind::Type => write!(f, "Type"),
+            APITokenKind::Number => write!(f, "Number"),
             APITokenKind::Identifier => write!(f, "Identifier"),
             APITokenKind::Struct => write!(f, "Struct"),
             APITokenKind::TagAnnotation => write!(f, "TagAnnotation"),
             APITokenKind::Server => write!(f, "Server"),
             APITokenKind::Service => write!(f, "Service"),
             APITokenKind::Handler => write!(f, "Handler"),
-            APITokenKind::HttpMethod => write!(f, "HttpMethod"),
             APITokenKind::RoutePath => write!(f, "RoutePath"),
             APITokenKind::RespReturns => write!(f, "RespReturns"),
+            APITokenKind::Error => write!(f, "Error"),
         }
     }
 }
@@ -187,4 +287,49 @@ mod tests {
             println!("{:#?}", token);
         }
     }
+
+    #[test]
+    fn it_tokenize_keeps_going_after_error() {
+        let source = "get /user?info returns (Info)";
+        let tokens = tokenize(source);
+
+        let error = tokens
+            .iter()
+            .find(|t| t.kind == APITokenKind::Error)
+            .unwrap();
+        assert_eq!(error.text(), "?");
+        assert_eq!(error.span, 9..10);
+        assert_eq!(tokens.last().unwrap().text(), ")");
+    }
+
+    #[test]
+    fn it_tokenize_comments() {
+        let source = r#"
+            // GetFormReq is the request of getForm
+            /* it is
+             * bound from the query */
+            type GetFormReq struct {
+                Name string `form:"name"` // the user name
+            }
+        "#;
+        let tokens = tokenize(source);
+
+        assert!(tokens.iter().all(|t| !matches!(
+            t.kind,
+            APITokenKind::LineComment | APITokenKind::BlockComment
+        )));
+        assert_eq!(
+            tokens[0].docs(),
+            vec![
+                "GetFormReq is the request of getForm",
+                "it is\nbound from the query"
+            ]
+        );
+        let tag = tokens
+            .iter()
+            .find(|t| t.kind == APITokenKind::TagAnnotation)
+            .unwrap();
+        assert_eq!(tag.trailing_comment, Some("// the user name"));
+        assert!(tokens.last().unwrap().comments.is_empty());
+    }
 }